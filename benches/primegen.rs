@@ -0,0 +1,37 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use num_bigint::BigUint;
+use primegen::{check_scaled_range, generate_primes_in_range, get_factors_biguint, RangeParams, DEFAULT_BASE, MAX_K};
+
+fn factors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_factors_biguint");
+    // m*360 at a mid scale, a u64 with a large prime factor for primal's sieve,
+    // and a semiprime past u64 that needs Pollard's rho
+    let inputs = [
+        ("m*360 (m=1e6)", BigUint::from(360_000_000u64)),
+        ("large prime*360 in u64", BigUint::from(4_294_967_291u64) * 360u32),
+        ("semiprime*360 past u64", BigUint::from(1_000_000_007u64) * BigUint::from(10_000_000_019u64) * 360u32),
+    ];
+    for (name, n) in &inputs {
+        group.bench_function(*name, |b| b.iter(|| get_factors_biguint(black_box(n))));
+    }
+    group.finish();
+}
+
+fn primes_in_range(c: &mut Criterion) {
+    let start = BigUint::from(36_000_000u64);
+    let end = BigUint::from(36_036_000u64);
+    c.bench_function("generate_primes_in_range (36e6, 36e6+36000]", |b| {
+        b.iter(|| generate_primes_in_range(black_box(&start), black_box(&end)))
+    });
+}
+
+fn scaled_range(c: &mut Criterion) {
+    let params = RangeParams::new(DEFAULT_BASE, MAX_K);
+    let m = BigUint::from(100_000u32);
+    c.bench_function("check_scaled_range m=100000", |b| {
+        b.iter(|| check_scaled_range(black_box(&m), &params, 100_000, None))
+    });
+}
+
+criterion_group!(benches, factors, primes_in_range, scaled_range);
+criterion_main!(benches);
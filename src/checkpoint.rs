@@ -0,0 +1,172 @@
+// Running totals of a scan, persisted so long runs can resume after a crash
+use crate::error::{Error, Result};
+use crate::{default_seq_offset, serde_helpers, Direction, FactorBase, Interval, Methods, RangeReport, Sampling, DEFAULT_SAMPLING_SEED};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// The prime with the largest nearest-candidate distance seen so far
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorstPrime {
+    #[serde(serialize_with = "serde_helpers::biguint", deserialize_with = "serde_helpers::biguint_from_str")]
+    pub k: BigUint,
+    #[serde(serialize_with = "serde_helpers::scale", deserialize_with = "serde_helpers::scale_from_json")]
+    pub m: BigUint,
+    #[serde(serialize_with = "serde_helpers::biguint", deserialize_with = "serde_helpers::biguint_from_str")]
+    pub prime: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // Parameters the totals were computed with; resuming with others is an error
+    pub base: u64,
+    pub max_k: u64,
+    pub max_primes_to_check: usize,
+    pub coprime_only: bool,
+    // Checkpoints from before sampling was configurable were written in auto mode
+    #[serde(default)]
+    pub sampling: Sampling,
+    // Checkpoints from before the factor cap existed kept every factor
+    #[serde(default)]
+    pub max_factors: Option<usize>,
+    // Checkpoints from before methods could be switched off used both
+    #[serde(default)]
+    pub methods: Methods,
+    // Checkpoints from before coverage could be one-sided counted both sides
+    #[serde(default)]
+    pub direction: Direction,
+    // Checkpoints from before the factor base could vary took factors of m*base
+    #[serde(default)]
+    pub factor_base: FactorBase,
+    // Checkpoints from before sampling was seeded are resumed with the default seed
+    #[serde(default)]
+    pub sampling_seed: u64,
+    // Checkpoints from before ranges could be closed left out their start
+    #[serde(default)]
+    pub interval: Interval,
+    // The given sequence offset, or None for the one derived from the base,
+    // which checkpoints from before the offset could be set always used
+    #[serde(default)]
+    pub seq_offset: Option<u64>,
+    // Changes the nearest distances and so the worst prime. Checkpoints from
+    // before the window could be resized kept factors within k of the range.
+    #[serde(default = "unit_window_factor")]
+    pub candidate_window_factor: f64,
+    // Highest scale whose report has been recorded, or None before the first
+    #[serde(serialize_with = "serde_helpers::optional_scale", deserialize_with = "serde_helpers::optional_scale_from_json")]
+    pub last_m: Option<BigUint>,
+    pub primes_checked: u64,
+    pub factors_only: u64,
+    pub seq_only: u64,
+    pub both_found: u64,
+    pub missed: u64,
+    pub worst: Option<WorstPrime>,
+}
+
+fn unit_window_factor() -> f64 {
+    1.0
+}
+
+impl Checkpoint {
+    pub fn new(base: u64, max_k: u64, max_primes_to_check: usize, coprime_only: bool) -> Self {
+        Checkpoint {
+            base,
+            max_k,
+            max_primes_to_check,
+            coprime_only,
+            sampling: Sampling::default(),
+            max_factors: None,
+            methods: Methods::default(),
+            direction: Direction::default(),
+            factor_base: FactorBase::default(),
+            sampling_seed: DEFAULT_SAMPLING_SEED,
+            interval: Interval::default(),
+            seq_offset: None,
+            candidate_window_factor: 1.0,
+            last_m: None,
+            primes_checked: 0,
+            factors_only: 0,
+            seq_only: 0,
+            both_found: 0,
+            missed: 0,
+            worst: None,
+        }
+    }
+
+    // Whether totals from this checkpoint can be continued with the given parameters
+    pub fn matches(&self, other: &Checkpoint) -> bool {
+        self.mismatch(other).is_none()
+    }
+
+    // The first parameter this checkpoint and other differ in, if any
+    pub fn mismatch(&self, other: &Checkpoint) -> Option<&'static str> {
+        [
+            ("base", self.base == other.base),
+            ("k", self.max_k == other.max_k),
+            ("max-primes", self.max_primes_to_check == other.max_primes_to_check),
+            ("coprime-candidates", self.coprime_only == other.coprime_only),
+            ("sampling mode", self.sampling == other.sampling),
+            ("factor cap", self.max_factors == other.max_factors),
+            ("methods", self.methods == other.methods),
+            ("direction", self.direction == other.direction),
+            ("factor base", self.factor_base == other.factor_base),
+            ("sampling seed", self.sampling_seed == other.sampling_seed),
+            ("interval", self.interval == other.interval),
+            ("sequence offset", self.sequence_offset() == other.sequence_offset()),
+            ("candidate window factor", self.candidate_window_factor == other.candidate_window_factor),
+        ]
+        .into_iter()
+        .find_map(|(setting, same)| (!same).then_some(setting))
+    }
+
+    // The sequence offset in effect, given or derived from the base, so a given
+    // offset equal to the derived one resumes a checkpoint that left it unset
+    pub fn sequence_offset(&self) -> u64 {
+        self.seq_offset.unwrap_or_else(|| default_seq_offset(self.base))
+    }
+
+    // Load the checkpoint at path to continue a run with the parameters of fresh
+    pub fn resume(path: &Path, fresh: &Checkpoint) -> Result<Self> {
+        let saved = Checkpoint::load(path)?;
+        if let Some(setting) = saved.mismatch(fresh) {
+            return Err(Error::CheckpointMismatch { path: path.to_path_buf(), setting });
+        }
+        Ok(saved)
+    }
+
+    // Fold one scale into the totals. Reports must be recorded in ascending m
+    // so the worst prime is the first scale to reach the largest distance.
+    pub fn record(&mut self, report: &RangeReport) {
+        if self.last_m.as_ref().is_none_or(|last| &report.m > last) {
+            self.last_m = Some(report.m.clone());
+        }
+        self.primes_checked += report.total_checked as u64;
+        self.factors_only += report.factors_only as u64;
+        self.seq_only += report.seq_only as u64;
+        self.both_found += report.both_found as u64;
+        self.missed += report.missed.len() as u64;
+
+        if let Some((k, prime)) = &report.minimal_k {
+            if self.worst.as_ref().is_none_or(|worst| k > &worst.k) {
+                self.worst = Some(WorstPrime { k: k.clone(), m: report.m.clone(), prime: prime.clone() });
+            }
+        }
+    }
+
+    // Write to a temporary file next to path and rename it into place, so a
+    // crash mid-write leaves the previous checkpoint intact
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let json = serde_json::to_string_pretty(self).expect("checkpoints are always serializable");
+        fs::write(&tmp, json).map_err(Error::io(&tmp))?;
+        fs::rename(&tmp, path).map_err(Error::io(path))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path).map_err(Error::io(path))?;
+        serde_json::from_str(&json)
+            .map_err(|source| Error::InvalidCheckpoint { path: path.to_path_buf(), source })
+    }
+}
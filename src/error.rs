@@ -0,0 +1,50 @@
+use num_bigint::BigUint;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Everything the fallible parts of the library can fail with
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+
+    // Writing to a caller-supplied writer, where there is no path to report
+    #[error(transparent)]
+    Write(#[from] io::Error),
+
+    #[error("{path} is not a valid checkpoint: {source}")]
+    InvalidCheckpoint { path: PathBuf, source: serde_json::Error },
+
+    // The setting is the first the checkpoint and the resumed run differ in
+    #[error("checkpoint {path} was written with a different setting: {setting}")]
+    CheckpointMismatch { path: PathBuf, setting: &'static str },
+
+    #[error("{path} is not a valid JSON report: {source}")]
+    InvalidReport { path: PathBuf, source: serde_json::Error },
+
+    #[error("report {path} was produced with a different setting: {setting}")]
+    ReportMismatch { path: PathBuf, setting: &'static str },
+
+    #[error("cannot parse '{input}' as {expected}")]
+    Parse { input: String, expected: &'static str },
+
+    #[error("cannot factor {n} within {max_rho_steps} steps of Pollard's rho")]
+    FactoringBudget { n: BigUint, max_rho_steps: u64 },
+
+    #[error("scale {m} spans {width} numbers beyond u64, too many to enumerate exhaustively")]
+    TooWideForExhaustive { m: BigUint, width: u64 },
+
+    #[error("cannot build thread pool: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // Attach the path an I/O error happened on
+    pub fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Error {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+}
@@ -0,0 +1,433 @@
+use crate::error::{Error, Result};
+use log::warn;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_prime::nt_funcs::is_prime;
+use num_traits::{One, Zero};
+use primal::Sieve;
+use std::cmp::Ordering;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use crate::util::{abs_diff, isqrt};
+
+// Primes below this bound are stripped by trial division before Pollard's rho,
+// unless set otherwise
+pub const DEFAULT_TRIAL_DIVISION_BOUND: u64 = 10_000;
+
+// Largest trial division bound accepted. Every factorization tries each prime
+// below the bound that is no larger than the square root of its cofactor, so
+// far past this one number spends seconds on divisions rho does in milliseconds.
+pub const MAX_TRIAL_DIVISION_BOUND: u64 = 10_000_000;
+
+static TRIAL_DIVISION_BOUND: AtomicU64 = AtomicU64::new(DEFAULT_TRIAL_DIVISION_BOUND);
+
+// The sieve of trial divisors and the bound it was built for
+static TRIAL_DIVISORS: Mutex<Option<(u64, Arc<Sieve>)>> = Mutex::new(None);
+
+// Set the largest trial divisor of every later factorization, at most
+// MAX_TRIAL_DIVISION_BOUND. Rho splits whatever is left, so a bound only moves
+// work between the two.
+pub fn set_trial_division_bound(bound: u64) {
+    TRIAL_DIVISION_BOUND.store(bound.min(MAX_TRIAL_DIVISION_BOUND), AtomicOrdering::Relaxed);
+}
+
+pub fn trial_division_bound() -> u64 {
+    TRIAL_DIVISION_BOUND.load(AtomicOrdering::Relaxed)
+}
+
+// A sieve of the primes up to the trial division bound, built on first use and
+// again only when the bound changes, so factorizations share it
+pub fn trial_division_sieve() -> Arc<Sieve> {
+    let bound = trial_division_bound();
+    let mut cached = TRIAL_DIVISORS.lock().unwrap();
+    match cached.as_ref() {
+        Some((cached_bound, sieve)) if *cached_bound == bound => sieve.clone(),
+        _ => {
+            let sieve = Arc::new(Sieve::new(bound as usize));
+            *cached = Some((bound, sieve.clone()));
+            sieve
+        }
+    }
+}
+
+// Composites of at least this many bits are split by pollard_rho_parallel when
+// there is more than one worker thread; smaller ones find a divisor with a
+// single polynomial in less time than the threads take to start
+pub const PARALLEL_RHO_BITS: u64 = 64;
+
+// Rho steps the factor subcommand takes before giving up. Rho needs about
+// sqrt(p) steps to find a prime factor p, so this splits off factors up to
+// about 10^14 in a few seconds; a product of two larger primes could take years.
+pub const DEFAULT_MAX_RHO_STEPS: u64 = 10_000_000;
+
+// Rho steps between the warnings of a factorization without a step limit,
+// half a minute or so
+const RHO_PROGRESS_STEPS: u64 = 100_000_000;
+
+// Rho steps taken so far on n, shared by every split and polynomial tried on it
+struct RhoSteps<'a> {
+    n: &'a BigUint,
+    taken: AtomicU64,
+    // None never stops, but warns every RHO_PROGRESS_STEPS steps instead
+    limit: Option<u64>,
+}
+
+impl RhoSteps<'_> {
+    // Count steps more, or return false once that would pass the limit
+    fn take(&self, steps: u64) -> bool {
+        let before = self.taken.fetch_add(steps, AtomicOrdering::Relaxed);
+        let after = before + steps;
+        match self.limit {
+            Some(limit) => after <= limit,
+            None => {
+                if before / RHO_PROGRESS_STEPS != after / RHO_PROGRESS_STEPS {
+                    warn!("still factoring {} after {} steps of Pollard's rho", self.n, after);
+                }
+                true
+            }
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.limit.is_some_and(|limit| self.taken.load(AtomicOrdering::Relaxed) > limit)
+    }
+}
+
+// Prime factorization of n as (prime, exponent) pairs in ascending prime order.
+// Rho runs for as long as n takes, warning every RHO_PROGRESS_STEPS steps so a
+// number too hard for it doesn't stall a run silently.
+pub fn prime_factorization(n: &BigUint) -> Vec<(BigUint, usize)> {
+    factorization_within(n, None).expect("rho without a step limit always finishes")
+}
+
+// prime_factorization, but failing with Error::FactoringBudget once rho has
+// taken max_rho_steps steps on n
+pub fn try_prime_factorization(n: &BigUint, max_rho_steps: u64) -> Result<Vec<(BigUint, usize)>> {
+    factorization_within(n, Some(max_rho_steps))
+        .ok_or_else(|| Error::FactoringBudget { n: n.clone(), max_rho_steps })
+}
+
+fn factorization_within(n: &BigUint, limit: Option<u64>) -> Option<Vec<(BigUint, usize)>> {
+    let mut primes = Vec::new();
+    if n <= &BigUint::one() {
+        return Some(Vec::new());
+    }
+
+    // Strip the small part with a sieve of trial divisors
+    let mut cofactor = n.clone();
+    for p in trial_division_sieve().primes_from(2) {
+        let p = BigUint::from(p);
+        if &p * &p > cofactor {
+            break;
+        }
+        while (&cofactor % &p).is_zero() {
+            cofactor /= &p;
+            primes.push(p.clone());
+        }
+    }
+
+    // Whatever is left has no factors below the bound; split it with rho
+    let steps = RhoSteps { n, taken: AtomicU64::new(0), limit };
+    if !cofactor.is_one() && !split_cofactor(cofactor, &mut primes, &steps) {
+        return None;
+    }
+
+    Some(group_prime_powers(primes))
+}
+
+// Every divisor of the number with the given factorization, sorted
+pub fn divisors_from_factorization(factorization: &[(BigUint, usize)]) -> Vec<BigUint> {
+    let mut divisors = vec![BigUint::one()];
+
+    // Generate all combinations of prime factors
+    for (prime, max_power) in factorization {
+        let mut new_divisors = Vec::new();
+
+        for divisor in &divisors {
+            let mut current = divisor.clone();
+            for _ in 0..*max_power {
+                current *= prime;
+                new_divisors.push(current.clone());
+            }
+        }
+
+        divisors.extend(new_divisors);
+    }
+
+    divisors.sort();
+    divisors
+}
+
+// Divisors of the number with the given factorization that lie in [low, high],
+// sorted. The prime powers are chosen depth first, and a branch is cut as soon
+// as its divisor exceeds high or can no longer reach low even with every
+// remaining prime at its full power, so far-off divisors are never built.
+pub fn divisors_in_range(factorization: &[(BigUint, usize)], low: &BigUint, high: &BigUint) -> Vec<BigUint> {
+    // remaining[i] is the product of the full prime powers from index i on
+    let mut remaining = vec![BigUint::one(); factorization.len() + 1];
+    for (i, (prime, power)) in factorization.iter().enumerate().rev() {
+        remaining[i] = &remaining[i + 1] * prime.pow(*power as u32);
+    }
+
+    let mut divisors = Vec::new();
+    collect_divisors_in_range(factorization, &remaining, BigUint::one(), low, high, &mut divisors);
+    divisors.sort();
+    divisors
+}
+
+fn collect_divisors_in_range(
+    factorization: &[(BigUint, usize)],
+    remaining: &[BigUint],
+    divisor: BigUint,
+    low: &BigUint,
+    high: &BigUint,
+    divisors: &mut Vec<BigUint>,
+) {
+    if &divisor > high || &(&divisor * &remaining[0]) < low {
+        return;
+    }
+    let Some(((prime, max_power), rest)) = factorization.split_first() else {
+        divisors.push(divisor);
+        return;
+    };
+
+    let mut current = divisor;
+    for power in 0..=*max_power {
+        if power > 0 {
+            current *= prime;
+            if &current > high {
+                break;
+            }
+        }
+        collect_divisors_in_range(rest, &remaining[1..], current.clone(), low, high, divisors);
+    }
+}
+
+// Number of divisors of the number with the given factorization
+pub fn divisor_count(factorization: &[(BigUint, usize)]) -> u64 {
+    factorization.iter().map(|&(_, power)| power as u64 + 1).product()
+}
+
+// Factorization of a*b from the factorizations of a and b, both in ascending prime order
+pub fn merge_factorizations(a: &[(BigUint, usize)], b: &[(BigUint, usize)]) -> Vec<(BigUint, usize)> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            Ordering::Less => {
+                merged.push(a[i].clone());
+                i += 1;
+            }
+            Ordering::Greater => {
+                merged.push(b[j].clone());
+                j += 1;
+            }
+            Ordering::Equal => {
+                merged.push((a[i].0.clone(), a[i].1 + b[j].1));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    merged.extend_from_slice(&a[i..]);
+    merged.extend_from_slice(&b[j..]);
+    merged
+}
+
+// Probable primes are accepted here; a wrong split would still multiply back to n
+fn is_probable_prime(n: &BigUint) -> bool {
+    is_prime(n, Some(crate::primality_certainty().config())).probably()
+}
+
+// Recursively split a cofactor with no small prime factors into primes, or
+// return false once rho runs out of steps
+fn split_cofactor(n: BigUint, primes: &mut Vec<BigUint>, steps: &RhoSteps) -> bool {
+    if n.is_one() {
+        return true;
+    }
+    if is_probable_prime(&n) {
+        primes.push(n);
+        return true;
+    }
+
+    // A perfect square defeats rho's cycle structure, so handle it directly
+    let root = isqrt(&n);
+    if &root * &root == n {
+        return split_cofactor(root.clone(), primes, steps) && split_cofactor(root, primes, steps);
+    }
+
+    let divisor = if n.bits() >= PARALLEL_RHO_BITS {
+        rho_parallel(&n, steps)
+    } else {
+        rho(&n, steps)
+    };
+    let Some(divisor) = divisor else {
+        return false;
+    };
+    let quotient = &n / &divisor;
+    split_cofactor(divisor, primes, steps) && split_cofactor(quotient, primes, steps)
+}
+
+// Brent's variant of Pollard's rho: returns a nontrivial divisor of a composite n
+pub fn pollard_rho(n: &BigUint) -> BigUint {
+    rho(n, &RhoSteps { n, taken: AtomicU64::new(0), limit: None }).expect("rho without a step limit always finishes")
+}
+
+fn rho(n: &BigUint, steps: &RhoSteps) -> Option<BigUint> {
+    if n.is_even() {
+        return Some(BigUint::from(2u32));
+    }
+
+    let one = BigUint::one();
+    let mut c = one.clone();
+    let never = AtomicBool::new(false);
+
+    // A failed cycle just means a retry with the next polynomial x^2 + c
+    loop {
+        if let Some(divisor) = brent_cycle(n, &c, &never, steps) {
+            return Some(divisor);
+        }
+        if steps.exhausted() {
+            return None;
+        }
+        c += &one;
+    }
+}
+
+// pollard_rho with one polynomial x^2 + c per worker thread at once, returning
+// the divisor of whichever finds one first; how long a polynomial takes varies
+// a lot, so the fastest of several usually beats x^2 + 1 alone. Serial builds
+// and single-thread pools fall back to pollard_rho.
+pub fn pollard_rho_parallel(n: &BigUint) -> BigUint {
+    rho_parallel(n, &RhoSteps { n, taken: AtomicU64::new(0), limit: None })
+        .expect("some polynomial splits every odd composite")
+}
+
+// The polynomials of one round share steps, so the limit bounds their total
+fn rho_parallel(n: &BigUint, steps: &RhoSteps) -> Option<BigUint> {
+    let threads = if cfg!(feature = "serial") { 1 } else { rayon::current_num_threads() as u64 };
+    if threads <= 1 || n.is_even() {
+        return rho(n, steps);
+    }
+
+    // Round r tries c = r*threads + 1 to (r + 1)*threads, until one succeeds
+    let mut round = 0;
+    loop {
+        let found = AtomicBool::new(false);
+        if let Some(divisor) = try_polynomials(n, round * threads + 1..=(round + 1) * threads, &found, steps) {
+            return Some(divisor);
+        }
+        if steps.exhausted() {
+            return None;
+        }
+        round += 1;
+    }
+}
+
+#[cfg(not(feature = "serial"))]
+fn try_polynomials(n: &BigUint, cs: std::ops::RangeInclusive<u64>, found: &AtomicBool, steps: &RhoSteps) -> Option<BigUint> {
+    use rayon::prelude::*;
+    cs.into_par_iter().find_map_any(|c| {
+        let divisor = brent_cycle(n, &BigUint::from(c), found, steps);
+        if divisor.is_some() {
+            found.store(true, AtomicOrdering::Relaxed);
+        }
+        divisor
+    })
+}
+
+#[cfg(feature = "serial")]
+fn try_polynomials(n: &BigUint, mut cs: std::ops::RangeInclusive<u64>, found: &AtomicBool, steps: &RhoSteps) -> Option<BigUint> {
+    cs.find_map(|c| brent_cycle(n, &BigUint::from(c), found, steps))
+}
+
+// One run of Brent's cycle search with x^2 + c. Gives up, returning None, when
+// the cycle closes without a divisor, once found is set by another search or
+// once steps runs out.
+fn brent_cycle(n: &BigUint, c: &BigUint, found: &AtomicBool, steps: &RhoSteps) -> Option<BigUint> {
+    // Number of steps whose differences are multiplied together per gcd
+    const BATCH: usize = 128;
+
+    let one = BigUint::one();
+    let step = |x: &BigUint| (x * x + c) % n;
+
+    let mut y = BigUint::from(2u32);
+    let mut x = y.clone();
+    let mut ys = y.clone();
+    let mut q = one.clone();
+    let mut g = one.clone();
+    let mut r = 1usize;
+
+    while g.is_one() {
+        if !steps.take(r as u64) {
+            return None;
+        }
+        x = y.clone();
+        for _ in 0..r {
+            y = step(&y);
+        }
+
+        let mut k = 0;
+        while k < r && g.is_one() {
+            if found.load(AtomicOrdering::Relaxed) || !steps.take(BATCH.min(r - k) as u64) {
+                return None;
+            }
+            ys = y.clone();
+            for _ in 0..BATCH.min(r - k) {
+                y = step(&y);
+                q = (q * abs_diff(&x, &y)) % n;
+            }
+            g = q.gcd(n);
+            k += BATCH;
+        }
+        r *= 2;
+    }
+
+    // The batch overshot; replay it one step at a time to find the divisor
+    if &g == n {
+        loop {
+            ys = step(&ys);
+            g = abs_diff(&x, &ys).gcd(n);
+            if !g.is_one() {
+                break;
+            }
+        }
+    }
+
+    if &g == n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+// Collapse a list of primes (in any order) into sorted (prime, exponent) pairs
+fn group_prime_powers(mut primes: Vec<BigUint>) -> Vec<(BigUint, usize)> {
+    primes.sort();
+    let mut grouped: Vec<(BigUint, usize)> = Vec::new();
+    for p in primes {
+        match grouped.last_mut() {
+            Some((last, power)) if *last == p => *power += 1,
+            _ => grouped.push((p, 1)),
+        }
+    }
+    grouped
+}
+
+// Factorization written as a product, e.g. 2^3 * 3^2 * 5; the empty product is 1
+pub fn format_factorization(factorization: &[(BigUint, usize)]) -> String {
+    if factorization.is_empty() {
+        return "1".to_string();
+    }
+    factorization.iter()
+        .map(|(p, e)| if *e == 1 { p.to_string() } else { format!("{}^{}", p, e) })
+        .collect::<Vec<_>>()
+        .join(" * ")
+}
+
+// Convenience for callers holding a u64 factorization from primal
+pub fn factorization_from_u64(factors: &[(usize, usize)]) -> Vec<(BigUint, usize)> {
+    factors.iter()
+        .map(|&(p, e)| (BigUint::from(p as u64), e))
+        .collect()
+}
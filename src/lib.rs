@@ -0,0 +1,2818 @@
+use num_bigint::{BigInt, BigUint};
+use num_traits::{Zero, One, ToPrimitive};
+use num_integer::Integer;
+use primal::Sieve;
+use par::*;
+use std::io::Write;
+use std::time::{Instant, Duration};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use num_prime::nt_funcs::is_prime;
+use num_prime::PrimalityTestConfig;
+use serde::{Deserialize, Serialize};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use log::{debug, error, info, trace, warn};
+
+pub use error::{Error, Result};
+pub use primality::{is_prime_reference, is_prime_u64_deterministic};
+pub use sequence::{Sequence, TermWindow, Terms};
+pub use util::{abs_diff, ceil_div, isqrt, SaturatingSub};
+
+pub mod checkpoint;
+pub mod error;
+pub mod factor;
+pub mod par;
+pub mod primality;
+pub mod prior;
+pub mod sequence;
+pub mod serde_helpers;
+pub mod sieve;
+pub mod util;
+
+// Keep the proven value for coverage
+pub const MAX_K: u64 = 180;
+
+// The modulus the conjecture is stated for
+pub const DEFAULT_BASE: u64 = 360;
+
+// Default bound of the shared sieve used by is_prime_biguint
+pub const DEFAULT_SIEVE_LIMIT: u64 = 10_000_000;
+
+// One sieve shared by every primality test, built on first use
+static SHARED_SIEVE: OnceLock<Sieve> = OnceLock::new();
+static SHARED_SIEVE_LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_SIEVE_LIMIT);
+
+// Set the bound of the shared sieve. Only takes effect before its first use.
+pub fn set_shared_sieve_limit(limit: u64) {
+    SHARED_SIEVE_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+// The shared sieve, building it now if this is the first use
+pub fn shared_sieve() -> &'static Sieve {
+    SHARED_SIEVE.get_or_init(|| Sieve::new(SHARED_SIEVE_LIMIT.load(Ordering::Relaxed) as usize))
+}
+
+// How thoroughly is_prime_biguint tests numbers beyond u64, where primality
+// is not proven: num_prime reports a number passing every test as
+// Primality::Probable, which counts as prime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Certainty {
+    // num_prime's default: strong probable prime tests to bases 2 and 3 and to
+    // three random bases, so results may differ between runs
+    Fast = 0,
+    // Baillie-PSW: a base-2 strong probable prime test and a strong Lucas test.
+    // No composite is known to pass it, and it gives the same answer every run
+    #[default]
+    Bpsw = 1,
+    // Baillie-PSW plus a strong probable prime test to one random base
+    Strict = 2,
+}
+
+impl Certainty {
+    pub fn config(self) -> PrimalityTestConfig {
+        match self {
+            Certainty::Fast => PrimalityTestConfig::default(),
+            Certainty::Bpsw => PrimalityTestConfig::bpsw(),
+            Certainty::Strict => PrimalityTestConfig::strict(),
+        }
+    }
+}
+
+impl std::str::FromStr for Certainty {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fast" => Ok(Certainty::Fast),
+            "bpsw" => Ok(Certainty::Bpsw),
+            "strict" => Ok(Certainty::Strict),
+            other => Err(Error::Parse { input: other.to_string(), expected: "a primality certainty" }),
+        }
+    }
+}
+
+impl std::fmt::Display for Certainty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Certainty::Fast => "fast",
+            Certainty::Bpsw => "bpsw",
+            Certainty::Strict => "strict",
+        })
+    }
+}
+
+// Decimal places of the seconds in printed durations, unless set otherwise
+pub const DEFAULT_DURATION_DECIMALS: u8 = 3;
+
+static DURATION_DECIMALS: AtomicU8 = AtomicU8::new(DEFAULT_DURATION_DECIMALS);
+
+// Set the decimal places of every duration printed from here on
+pub fn set_duration_decimals(decimals: u8) {
+    DURATION_DECIMALS.store(decimals, Ordering::Relaxed);
+}
+
+// A duration in seconds with a fixed number of decimals, e.g. 0.250s, so
+// printed timings share one unit whatever their size
+pub fn format_duration(duration: Duration) -> String {
+    format!("{:.*}s", DURATION_DECIMALS.load(Ordering::Relaxed) as usize, duration.as_secs_f64())
+}
+
+// Seed of the sampling RNG unless set otherwise, so that runs without a seed
+// check the same numbers every time
+pub const DEFAULT_SAMPLING_SEED: u64 = 0;
+
+// The RNG behind the sampling decisions for the range starting at
+// range_start. Each range draws from its own stream of the seed, so its
+// sample is the same whichever order or thread the ranges are checked in.
+pub fn sampling_rng(seed: u64, range_start: &BigUint) -> impl Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(range_start.iter_u64_digits().fold(0, |stream, digit| stream.rotate_left(17) ^ digit));
+    rng
+}
+
+static PRIMALITY_CERTAINTY: AtomicU8 = AtomicU8::new(Certainty::Bpsw as u8);
+
+// Set the certainty of every later primality test beyond the shared sieve
+pub fn set_primality_certainty(certainty: Certainty) {
+    PRIMALITY_CERTAINTY.store(certainty as u8, Ordering::Relaxed);
+}
+
+pub fn primality_certainty() -> Certainty {
+    match PRIMALITY_CERTAINTY.load(Ordering::Relaxed) {
+        0 => Certainty::Fast,
+        2 => Certainty::Strict,
+        _ => Certainty::Bpsw,
+    }
+}
+
+// Which ends of a scale's range belong to it. Either way range_end does;
+// a closed range also takes range_start, which a half-open one leaves to the
+// scale below, so adjacent closed ranges share the number between them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interval {
+    // (range_start, range_end]
+    #[default]
+    HalfOpen,
+    // [range_start, range_end]
+    Closed,
+}
+
+impl Interval {
+    pub fn contains(self, n: &BigUint, range_start: &BigUint, range_end: &BigUint) -> bool {
+        n <= range_end && match self {
+            Interval::HalfOpen => n > range_start,
+            Interval::Closed => n >= range_start,
+        }
+    }
+
+    // The number just below the range, past which its primes are generated
+    pub fn exclusive_start(self, range_start: &BigUint) -> BigUint {
+        match self {
+            Interval::HalfOpen => range_start.clone(),
+            Interval::Closed => range_start.saturating_sub(&BigUint::one()),
+        }
+    }
+
+    // The range written with its brackets, e.g. (720, 1080] or [720, 1080]
+    pub fn format_range(self, range_start: &BigUint, range_end: &BigUint) -> String {
+        match self {
+            Interval::HalfOpen => format!("({}, {}]", range_start, range_end),
+            Interval::Closed => format!("[{}, {}]", range_start, range_end),
+        }
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "half-open" => Ok(Interval::HalfOpen),
+            "closed" => Ok(Interval::Closed),
+            other => Err(Error::Parse { input: other.to_string(), expected: "closed or half-open" }),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Interval::HalfOpen => "half-open",
+            Interval::Closed => "closed",
+        })
+    }
+}
+
+// Optimization constants
+// Default number of candidates tested for primality, and of primes checked for
+// coverage, per parallel batch; larger chunks spread rayon's overhead further
+pub const PRIME_TEST_CHUNK_SIZE: usize = 10_000;
+// Default number of primes checked between progress bar updates and trace ticks
+pub const REPORT_INTERVAL: usize = 1_000;
+
+// Primes checked per scale by scan_scales unless set otherwise, as by the scan's --max-primes
+pub const DEFAULT_MAX_PRIMES: usize = 100_000;
+
+// Scales checked per batch of a ScaleScan; with ParallelMode::Scales a batch is
+// widened to at least one scale per worker thread
+pub const SCALE_BATCH_LEN: u64 = 10;
+
+// Prime factorization of n as (prime, exponent) pairs in ascending prime order
+pub fn factorize_biguint(n: &BigUint) -> Vec<(BigUint, usize)> {
+    // For larger numbers, strip the primes below the trial division bound and
+    // split the remaining cofactor with Pollard's rho
+    small_factorization(n).unwrap_or_else(|| factor::prime_factorization(n))
+}
+
+// factorize_biguint, but failing with Error::FactoringBudget instead of running
+// on once rho has taken max_rho_steps steps
+pub fn try_factorize_biguint(n: &BigUint, max_rho_steps: u64) -> Result<Vec<(BigUint, usize)>> {
+    small_factorization(n).map_or_else(|| factor::try_prime_factorization(n, max_rho_steps), Ok)
+}
+
+// Numbers whose square root is within the trial division bound are factored
+// by primal with the sieve of trial divisors, which is enough to factor n
+// completely. Larger ones would need a sieve of up to 2^32 numbers, so they go to rho.
+fn small_factorization(n: &BigUint) -> Option<Vec<(BigUint, usize)>> {
+    let n_u64 = n.to_u64().filter(|n_u64| isqrt(n_u64) < factor::trial_division_bound())?;
+    let small_factors = factor::trial_division_sieve().factor(n_u64 as usize).ok()?;
+    Some(factor::factorization_from_u64(&small_factors))
+}
+
+// All divisors of n, sorted ascending
+pub fn get_factors_biguint(n: &BigUint) -> Vec<BigUint> {
+    factor::divisors_from_factorization(&factorize_biguint(n))
+}
+
+// Factorizations of every base seen so far, shared by all scales
+type FactorizationCache = Mutex<HashMap<u64, Arc<Vec<(BigUint, usize)>>>>;
+static BASE_FACTORIZATIONS: OnceLock<FactorizationCache> = OnceLock::new();
+
+// Factorization of the base, computed once per base and then reused
+pub fn base_factorization(base: u64) -> Arc<Vec<(BigUint, usize)>> {
+    let cache = BASE_FACTORIZATIONS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap();
+    cache.entry(base)
+        .or_insert_with(|| Arc::new(factorize_biguint(&BigUint::from(base))))
+        .clone()
+}
+
+// All divisors of m*base, sorted ascending. Only m is factored; the cached
+// factorization of the base is merged in, which avoids factoring the full product
+pub fn get_factors_of_scaled_base(m: &BigUint, base: u64) -> Vec<BigUint> {
+    divisors_of_product(&base_factorization(base), m)
+}
+
+// All divisors of m times the number with the given factorization, sorted
+// ascending, e.g. of m*360 from 360 = 2^3 * 3^2 * 5. Only m is factored.
+pub fn divisors_of_product(base_factorization: &[(BigUint, usize)], m: &BigUint) -> Vec<BigUint> {
+    factor::divisors_from_factorization(&factor::merge_factorizations(&factorize_biguint(m), base_factorization))
+}
+
+// Factorization of m*base from that of m and the cached one of the base
+pub fn factorization_of_scaled_base(m: &BigUint, base: u64) -> Vec<(BigUint, usize)> {
+    factor::merge_factorizations(&factorize_biguint(m), &base_factorization(base))
+}
+
+// Terms of the default triangular sequence seeded at base, up to max_value
+pub fn recursive_sequence_generator_optimized(base: &BigUint, max_value: &BigUint) -> Vec<BigUint> {
+    Sequence::Triangular.terms(base, max_value)
+}
+
+// Candidates must be sorted ascending for both proximity checks below. Only the
+// two neighbours of prime's insertion point can be nearest, so each check is a
+// binary search rather than a scan.
+pub fn check_proximity_biguint(prime: &BigUint, candidates: &[BigUint], max_k: &BigUint) -> bool {
+    nearest_candidate(prime, candidates).is_some_and(|(_, diff)| &diff <= max_k)
+}
+
+// The candidate closest to prime and its absolute distance, or None if there
+// are no candidates. Ties go to the smaller candidate. The binary search needs
+// candidates sorted ascending.
+pub fn nearest_candidate(prime: &BigUint, candidates: &[BigUint]) -> Option<(BigUint, BigUint)> {
+    debug_assert!(is_strictly_ascending(candidates), "candidates must be sorted and free of duplicates");
+    nearest_index(prime, candidates, |candidate| candidate).map(|(i, diff)| (candidates[i].clone(), diff))
+}
+
+// nearest_candidate among the candidates on one side of prime only: those at or
+// below it for Direction::Above, at or above it for Direction::Below. A
+// candidate equal to the prime lies on both sides.
+pub fn nearest_candidate_directed(
+    prime: &BigUint,
+    candidates: &[BigUint],
+    direction: Direction,
+) -> Option<(BigUint, BigUint)> {
+    debug_assert!(is_strictly_ascending(candidates), "candidates must be sorted and free of duplicates");
+    match direction {
+        Direction::Both => nearest_candidate(prime, candidates),
+        Direction::Above => {
+            let idx = candidates.partition_point(|candidate| candidate <= prime);
+            idx.checked_sub(1).map(|i| (candidates[i].clone(), abs_diff(prime, &candidates[i])))
+        }
+        Direction::Below => {
+            let idx = candidates.partition_point(|candidate| candidate < prime);
+            candidates.get(idx).map(|candidate| (candidate.clone(), abs_diff(candidate, prime)))
+        }
+    }
+}
+
+// A forward-only position in candidates sorted ascending, answering
+// nearest_candidate_directed for primes given in ascending order. Each query
+// steps past the candidates below its prime instead of binary searching, so a
+// sweep over n primes and c candidates takes O(n + c) comparisons.
+pub struct CandidateSweep<'a> {
+    candidates: &'a [BigUint],
+    // Number of candidates below the last prime asked about
+    below: usize,
+}
+
+impl<'a> CandidateSweep<'a> {
+    pub fn new(candidates: &'a [BigUint]) -> Self {
+        debug_assert!(is_strictly_ascending(candidates), "candidates must be sorted and free of duplicates");
+        CandidateSweep { candidates, below: 0 }
+    }
+
+    // nearest_candidate_directed(prime, candidates, direction); prime must be
+    // at least the prime of the previous call
+    pub fn nearest(&mut self, prime: &BigUint, direction: Direction) -> Option<(BigUint, BigUint)> {
+        let candidates = self.candidates;
+        while candidates.get(self.below).is_some_and(|candidate| candidate < prime) {
+            self.below += 1;
+        }
+        let idx = self.below;
+        match direction {
+            Direction::Both => nearest_around(prime, candidates, idx, |candidate| candidate)
+                .map(|(i, diff)| (candidates[i].clone(), diff)),
+            Direction::Above => {
+                // A candidate equal to the prime counts as lying below it
+                let at_or_below = idx + usize::from(candidates.get(idx) == Some(prime));
+                at_or_below.checked_sub(1).map(|i| (candidates[i].clone(), abs_diff(prime, &candidates[i])))
+            }
+            Direction::Below => candidates.get(idx).map(|candidate| (candidate.clone(), abs_diff(candidate, prime))),
+        }
+    }
+}
+
+// nearest_candidate_directed for every prime of an ascending list, found in
+// one sweep over primes and candidates together
+pub fn nearest_candidates_sweep(
+    primes: &[BigUint],
+    candidates: &[BigUint],
+    direction: Direction,
+) -> Vec<Option<(BigUint, BigUint)>> {
+    debug_assert!(primes.windows(2).all(|pair| pair[0] <= pair[1]), "primes must be ascending");
+    let mut sweep = CandidateSweep::new(candidates);
+    primes.iter().map(|prime| sweep.nearest(prime, direction)).collect()
+}
+
+// check_proximity_biguint counting only the candidates on the given side of prime
+pub fn check_proximity_directed(prime: &BigUint, candidates: &[BigUint], max_k: &BigUint, direction: Direction) -> bool {
+    nearest_candidate_directed(prime, candidates, direction).is_some_and(|(_, diff)| &diff <= max_k)
+}
+
+// prime - candidate: positive when the prime lies above the candidate
+pub fn signed_offset(prime: &BigUint, candidate: &BigUint) -> BigInt {
+    BigInt::from(prime.clone()) - BigInt::from(candidate.clone())
+}
+
+// Index of the entry whose value is nearest to prime, with the distance, among
+// entries sorted ascending by value; ties go to the smaller value
+fn nearest_index<T>(prime: &BigUint, entries: &[T], value: impl Fn(&T) -> &BigUint) -> Option<(usize, BigUint)> {
+    let idx = entries.partition_point(|entry| value(entry) < prime);
+    nearest_around(prime, entries, idx, value)
+}
+
+// nearest_index given idx, the number of entries below prime
+fn nearest_around<T>(prime: &BigUint, entries: &[T], idx: usize, value: impl Fn(&T) -> &BigUint) -> Option<(usize, BigUint)> {
+    let below = idx.checked_sub(1).map(|i| (i, abs_diff(prime, value(&entries[i]))));
+    let above = entries.get(idx).map(|entry| (idx, abs_diff(value(entry), prime)));
+
+    match (below, above) {
+        (Some(below), Some(above)) => Some(if above.1 < below.1 { above } else { below }),
+        (below, above) => below.or(above),
+    }
+}
+
+// Both candidate sets, each sorted ascending without duplicates, merged into
+// one such list; a number in both sets is listed once, as CandidateSource::Both
+pub fn merge_candidates(factors: &[BigUint], seq_terms: &[BigUint]) -> Vec<(BigUint, CandidateSource)> {
+    let mut merged = Vec::with_capacity(factors.len() + seq_terms.len());
+    let (mut i, mut j) = (0, 0);
+    while i < factors.len() && j < seq_terms.len() {
+        match factors[i].cmp(&seq_terms[j]) {
+            std::cmp::Ordering::Less => {
+                merged.push((factors[i].clone(), CandidateSource::Factor));
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                merged.push((seq_terms[j].clone(), CandidateSource::Sequence));
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                merged.push((factors[i].clone(), CandidateSource::Both));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    merged.extend(factors[i..].iter().map(|f| (f.clone(), CandidateSource::Factor)));
+    merged.extend(seq_terms[j..].iter().map(|t| (t.clone(), CandidateSource::Sequence)));
+    merged
+}
+
+// The candidate of a merged set nearest to prime, with its distance and the set
+// it comes from, or None if there are no candidates. One binary search answers
+// for both methods; ties go to the smaller candidate.
+pub fn nearest_merged(prime: &BigUint, merged: &[(BigUint, CandidateSource)]) -> Option<(BigUint, BigUint, CandidateSource)> {
+    nearest_index(prime, merged, |(candidate, _)| candidate).map(|(i, diff)| {
+        let (candidate, source) = &merged[i];
+        (candidate.clone(), diff, *source)
+    })
+}
+
+// nearest_merged, but only if the candidate lies within max_k of prime. The
+// per-method checks above remain for telling factor and sequence coverage apart.
+pub fn check_proximity_combined(
+    prime: &BigUint,
+    merged: &[(BigUint, CandidateSource)],
+    max_k: &BigUint,
+) -> Option<(BigUint, BigUint, CandidateSource)> {
+    nearest_merged(prime, merged).filter(|(_, diff, _)| diff <= max_k)
+}
+
+// found as a percentage of checked, or None when nothing was checked
+pub fn coverage_percent(found: usize, checked: usize) -> Option<f64> {
+    (checked > 0).then(|| found as f64 * 100.0 / checked as f64)
+}
+
+// Whether every candidate is smaller than the next, i.e. sorted and deduplicated
+pub fn is_strictly_ascending(candidates: &[BigUint]) -> bool {
+    candidates.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+// Whichever of two nearest-candidate results is closer
+fn closer(
+    a: Option<(BigUint, BigUint)>,
+    b: Option<(BigUint, BigUint)>,
+) -> Option<(BigUint, BigUint)> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.1 < a.1 { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+// Whether n is prime. Numbers up to the shared sieve's bound are looked up in
+// it and the rest of the u64 range is proven by deterministic Miller-Rabin.
+// Larger numbers go to num_prime at the configured certainty, where
+// Primality::Probable (passed every test) counts as prime and only
+// Primality::No as composite.
+pub fn is_prime_biguint(n: &BigUint) -> bool {
+    // Convert small numbers to u64 for faster checking
+    if let Some(n_u64) = n.to_u64() {
+        if n_u64 <= 1 {
+            return false;
+        }
+        
+        // Use the shared sieve for small numbers (faster); anything above
+        // its bound is still quick to prove with fixed witnesses
+        let sieve = shared_sieve();
+        if n_u64 <= sieve.upper_bound() as u64 {
+            return sieve.is_prime(n_u64 as usize);
+        }
+        return is_prime_u64_deterministic(n_u64);
+    }
+    
+    is_prime(n, Some(primality_certainty().config())).probably()
+}
+
+// Whether is_prime_biguint's verdict on n is a proof. Every u64 is decided by
+// the sieve or deterministic Miller-Rabin, but num_prime only proves numbers
+// below 2^64 and answers Primality::Probable for any larger prime, whatever
+// the certainty, so a prime beyond u64 is only a probable prime.
+pub fn is_primality_proven(n: &BigUint) -> bool {
+    n.bits() <= 64
+}
+
+// The largest prime below n, or None if n <= 2. Odd numbers are tested
+// downwards, which takes about ln(n)/2 primality tests on average.
+pub fn previous_prime(n: &BigUint) -> Option<BigUint> {
+    let two = BigUint::from(2u32);
+    if n <= &two {
+        return None;
+    }
+    if n == &BigUint::from(3u32) {
+        return Some(two);
+    }
+    let mut candidate = n - 1u32;
+    if candidate.is_even() {
+        candidate -= 1u32;
+    }
+    while !is_prime_biguint(&candidate) {
+        candidate -= 2u32;
+    }
+    Some(candidate)
+}
+
+// Ranges wider than this that don't fit the sieve are sampled instead of
+// checked exhaustively
+pub const SAMPLING_THRESHOLD: u64 = 1_000_000;
+
+// A sampled range is covered by this many evenly spaced blocks of
+// consecutive odd numbers, so every part of the range is represented
+const SAMPLE_BLOCKS: u64 = 1_000;
+const SAMPLE_BLOCK_LEN: u64 = 1_000;
+
+// How a range's primes are enumerated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sampling {
+    // Enumerate every prime, except in ranges beyond u64 wider than SAMPLING_THRESHOLD
+    #[default]
+    Auto,
+    // Enumerate every prime of every range, however wide
+    Exhaustive,
+    // Test this many evenly spread odd numbers of any range holding more
+    Sample(u64),
+}
+
+impl Sampling {
+    // Whether a range with these bounds gets a sample rather than every prime
+    pub fn applies_to(&self, range_start: &BigUint, range_end: &BigUint) -> bool {
+        match *self {
+            Sampling::Auto => is_sampled_range(range_start, range_end),
+            Sampling::Exhaustive => false,
+            Sampling::Sample(count) => range_end.saturating_sub(range_start) / 2u32 > BigUint::from(count),
+        }
+    }
+
+    // Number of odd numbers tested in a range this applies to
+    pub fn sample_size(&self) -> u64 {
+        match *self {
+            Sampling::Sample(count) => count,
+            Sampling::Auto | Sampling::Exhaustive => SAMPLE_BLOCKS * SAMPLE_BLOCK_LEN,
+        }
+    }
+}
+
+impl std::fmt::Display for Sampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sampling::Auto => write!(f, "auto"),
+            Sampling::Exhaustive => write!(f, "exhaustive"),
+            Sampling::Sample(count) => write!(f, "sample of {}", count),
+        }
+    }
+}
+
+// How a checked range's primes came to be, which tells a range without primes
+// apart from one whose primes were only looked for in part of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimeGenOutcome {
+    // Every number of the range was enumerated and at least one is prime, or
+    // the check stopped (timed out or by fail-fast) before enumerating them all
+    Exhaustive,
+    // Only n odd numbers of the range were tested, so finding no primes among
+    // them says nothing about the range
+    Sampled { n: u64 },
+    // Every number of the range was enumerated and none is prime
+    Empty,
+}
+
+// Whether generate_primes_in_range returns a sample rather than every prime in the range
+pub fn is_sampled_range(range_start: &BigUint, range_end: &BigUint) -> bool {
+    if range_start.to_u64().is_some() && range_end.to_u64().is_some() {
+        return false;
+    }
+    let range_size = range_end.saturating_sub(range_start);
+    range_size > BigUint::from(SAMPLING_THRESHOLD)
+}
+
+// Odd candidates from SAMPLE_BLOCKS blocks spread evenly across the range,
+// placed with the default seed. The blocks depend only on the bounds and the
+// seed, so repeated runs check the same numbers.
+pub fn sample_candidates(range_start: &BigUint, range_end: &BigUint) -> Vec<BigUint> {
+    sample_odd_numbers(range_start, range_end, SAMPLE_BLOCKS * SAMPLE_BLOCK_LEN)
+}
+
+// Up to count odd candidates in SAMPLE_BLOCKS blocks of consecutive odd numbers
+// spread evenly across the range, or in count blocks of one if that is fewer,
+// placed with the default seed
+pub fn sample_odd_numbers(range_start: &BigUint, range_end: &BigUint, count: u64) -> Vec<BigUint> {
+    sample_odd_numbers_with(range_start, range_end, count, DEFAULT_SAMPLING_SEED)
+}
+
+// sample_odd_numbers with the given seed. The range is cut into one stretch
+// per block, and the seed's RNG picks where in its stretch each block starts,
+// so every part of the range is still represented.
+pub fn sample_odd_numbers_with(range_start: &BigUint, range_end: &BigUint, count: u64, seed: u64) -> Vec<BigUint> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let blocks = count.min(SAMPLE_BLOCKS);
+    let block_len = ceil_div(&count, &blocks);
+    let stride = range_end.saturating_sub(range_start) / blocks;
+    // How far a block of block_len odd numbers can move within its stretch
+    let slack = stride.saturating_sub(&BigUint::from(2 * block_len)).to_u64().unwrap_or(u64::MAX);
+    let mut rng = sampling_rng(seed, range_start);
+    let mut candidates = Vec::with_capacity(count as usize);
+
+    for block in 0..blocks {
+        let block_start = range_start + &stride * block;
+        // Blocks of a range barely wider than the sample must not overlap
+        let next_block = (block + 1 < blocks).then(|| &block_start + &stride);
+        // Samples are drawn from (range_start, range_end], so even the first block starts past range_start
+        let mut current = block_start + rng.gen_range(0..=slack) + 1u32;
+        if current.is_even() {
+            current += 1u32;
+        }
+        for _ in 0..block_len {
+            if &current > range_end || next_block.as_ref().is_some_and(|next| &current >= next) {
+                break;
+            }
+            candidates.push(current.clone());
+            current += 2u32;
+        }
+    }
+
+    candidates.truncate(count as usize);
+    candidates
+}
+
+// Err if an exhaustive check of scale m would have to enumerate a range that
+// Sampling::Auto samples because it is too wide beyond u64 to test every odd number
+pub fn ensure_exhaustive(m: &BigUint, base: u64) -> Result<()> {
+    let (range_start, range_end) = range_bounds(m, base);
+    if is_sampled_range(&range_start, &range_end) {
+        return Err(Error::TooWideForExhaustive { m: m.clone(), width: base });
+    }
+    Ok(())
+}
+
+pub fn generate_primes_in_range(range_start: &BigUint, range_end: &BigUint) -> Vec<BigUint> {
+    generate_primes_in_range_with(range_start, range_end, Sampling::Auto)
+}
+
+// The primes of (range_start, range_end], or of a sample of it as chosen by sampling
+pub fn generate_primes_in_range_with(range_start: &BigUint, range_end: &BigUint, sampling: Sampling) -> Vec<BigUint> {
+    primes_in_range_iter_with(range_start, range_end, sampling, DEFAULT_SAMPLING_SEED, Interval::HalfOpen, PRIME_TEST_CHUNK_SIZE).collect()
+}
+
+// The primes of (range_start, range_end] in ascending order, generated as they
+// are consumed so only a segment or chunk of candidates is held at once
+pub fn primes_in_range_iter(range_start: &BigUint, range_end: &BigUint) -> impl Iterator<Item = BigUint> + Send {
+    primes_in_range_iter_with(range_start, range_end, Sampling::Auto, DEFAULT_SAMPLING_SEED, Interval::HalfOpen, PRIME_TEST_CHUNK_SIZE)
+}
+
+// The primes of the range with the given interval, so [range_start, range_end]
+// for Interval::Closed. Candidates the sieve can't handle are tested chunk_size
+// at a time, which only changes how the work is batched, never which primes
+// come out. A sampled range has its sample placed with seed.
+pub fn primes_in_range_iter_with(
+    range_start: &BigUint,
+    range_end: &BigUint,
+    sampling: Sampling,
+    seed: u64,
+    interval: Interval,
+    chunk_size: usize,
+) -> Box<dyn Iterator<Item = BigUint> + Send> {
+    primes_after(&interval.exclusive_start(range_start), range_end, sampling, seed, chunk_size)
+}
+
+// The primes of (range_start, range_end], which every path below generates
+// from the numbers past range_start
+fn primes_after(
+    range_start: &BigUint,
+    range_end: &BigUint,
+    sampling: Sampling,
+    seed: u64,
+    chunk_size: usize,
+) -> Box<dyn Iterator<Item = BigUint> + Send> {
+    // If the range is too large, test an even spread of samples instead of every number
+    if sampling.applies_to(range_start, range_end) {
+        let candidates = sample_odd_numbers_with(range_start, range_end, sampling.sample_size(), seed);
+        return Box::new(filter_primes_chunked(candidates.into_iter(), chunk_size));
+    }
+
+    // If the range fits in u64, sieve it exhaustively one segment at a time
+    if let (Some(start_u64), Some(end_u64)) = (range_start.to_u64(), range_end.to_u64()) {
+        let Some(first) = start_u64.checked_add(1) else {
+            return Box::new(std::iter::empty());
+        };
+        return Box::new(sieve::segments(first, end_u64).flatten().map(BigUint::from));
+    }
+
+    // For smaller but still large ranges, check each odd number after range_start
+    let mut start = range_start + 1u32;
+    if start.is_even() {
+        start += 1u32;
+    }
+    let end = range_end.clone();
+    let odd_numbers = std::iter::successors(Some(start), |n| Some(n + 2u32))
+        .take_while(move |n| n <= &end);
+
+    // Special case for 2 if it's in the range
+    let two = BigUint::from(2u32);
+    let two = (range_start < &two && range_end >= &two).then_some(two);
+
+    Box::new(two.into_iter().chain(filter_primes_chunked(odd_numbers, chunk_size)))
+}
+
+// The primes among ascending candidates, tested in parallel chunk_size at a time
+fn filter_primes_chunked(
+    mut candidates: impl Iterator<Item = BigUint> + Send,
+    chunk_size: usize,
+) -> impl Iterator<Item = BigUint> + Send {
+    let chunk_size = chunk_size.max(1);
+    std::iter::from_fn(move || {
+        let chunk: Vec<BigUint> = candidates.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            return None;
+        }
+        Some(chunk.into_par_iter().filter(is_prime_biguint).collect::<Vec<_>>())
+    })
+    .flatten()
+}
+
+// The count items of total evenly spaced by index, the middle one of each of
+// count equal runs, e.g. indices 1, 4 and 7 for 3 of 9. Every item is taken if
+// count >= total.
+pub fn evenly_spaced<T>(items: impl Iterator<Item = T>, total: usize, count: usize) -> impl Iterator<Item = T> {
+    let (total, count) = (total as u128, count.min(total) as u128);
+    // Index of the j-th item taken: the middle of the j-th of count runs
+    let target = move |j: u128| ((2 * j + 1) * total / (2 * count)) as usize;
+    let mut taken = 0;
+    items.enumerate().filter_map(move |(i, item)| {
+        if taken < count && i == target(taken) {
+            taken += 1;
+            Some(item)
+        } else {
+            None
+        }
+    })
+}
+
+// The count items of total picked one from each of count equal runs of
+// indices, at random with rng, e.g. one of 0-2, one of 3-5 and one of 6-8 for
+// 3 of 9. Every item is taken if count >= total.
+pub fn stratified_sample<T>(items: impl Iterator<Item = T>, total: usize, count: usize, rng: &mut impl Rng) -> impl Iterator<Item = T> {
+    let (total, count) = (total as u128, count.min(total) as u128);
+    let targets: Vec<usize> = (0..count)
+        .map(|j| rng.gen_range((j * total / count) as usize..((j + 1) * total / count) as usize))
+        .collect();
+    let mut taken = 0;
+    items.enumerate().filter_map(move |(i, item)| {
+        if taken < targets.len() && i == targets[taken] {
+            taken += 1;
+            Some(item)
+        } else {
+            None
+        }
+    })
+}
+
+// The primes of the range a coverage check takes, how many the range holds
+// when that is already known, and whether they come from a sample of it; the
+// range includes range_start if params.interval is closed. Without a cap they are streamed
+// as they are found. With one, up to max_primes_to_check + 1 primes are held
+// back first: a range with no more has them all checked, while a larger range
+// is counted to its end and streamed again for a stratified sample of
+// max_primes_to_check of them drawn with the sampling seed, so the top of the
+// range is checked as well as the bottom. The sampling settings and chunk size
+// are taken from params.
+pub fn primes_to_check(
+    range_start: &BigUint,
+    range_end: &BigUint,
+    params: &RangeParams,
+    max_primes_to_check: usize,
+) -> (Box<dyn Iterator<Item = BigUint> + Send>, Option<usize>, bool) {
+    let RangeParams { sampling, sampling_seed, interval, chunk_size, .. } = *params;
+    let chunk_size = chunk_size.max(1);
+    // Both the primes and the sampled flag are decided from the numbers past this
+    let after = interval.exclusive_start(range_start);
+    let sampled = sampling.applies_to(&after, range_end);
+    let mut primes = primes_after(&after, range_end, sampling, sampling_seed, chunk_size);
+    if max_primes_to_check == usize::MAX {
+        return (primes, None, sampled);
+    }
+
+    let held: Vec<BigUint> = primes.by_ref().take(max_primes_to_check.saturating_add(1)).collect();
+    if held.len() <= max_primes_to_check {
+        let total = held.len();
+        return (Box::new(held.into_iter()), Some(total), sampled);
+    }
+    let total = held.len() + primes.count();
+    let again = primes_after(&after, range_end, sampling, sampling_seed, chunk_size);
+    let mut rng = sampling_rng(sampling_seed, range_start);
+    (Box::new(stratified_sample(again, total, max_primes_to_check, &mut rng)), Some(total), sampled)
+}
+
+// Range boundaries and both candidate sets for one scale, each sorted ascending
+// without duplicates
+pub struct PreparedRange {
+    pub range_start: BigUint,
+    pub range_end: BigUint,
+    pub factors_base: BigUint,
+    pub total_factors: usize,
+    pub relevant_factors: Vec<BigUint>,
+    // Relevant factors left out by RangeParams::max_factors
+    pub factors_dropped: usize,
+    pub seq_base: BigUint,
+    pub seq_terms: Vec<BigUint>,
+    pub factor_gen_time: Duration,
+    pub seq_gen_time: Duration,
+}
+
+// Which level of a sweep runs in parallel. Nesting both oversubscribes the pool
+// with work that is too fine-grained to pay for itself, so only one is used.
+// The default is the binary's, so scan_scales checks scales as a scan does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParallelMode {
+    // Check several scales at once, each one's primes sequentially; best when a
+    // sweep covers many scales with few primes each
+    #[default]
+    Scales,
+    // Check one scale at a time, its primes in parallel; best for few, wide scales
+    Primes,
+}
+
+impl std::str::FromStr for ParallelMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "scales" => Ok(ParallelMode::Scales),
+            "primes" => Ok(ParallelMode::Primes),
+            other => Err(Error::Parse { input: other.to_string(), expected: "a parallel mode" }),
+        }
+    }
+}
+
+// Which candidate methods a coverage check uses. With only one, the other's
+// candidates are never generated and a prime counts as covered only when the
+// selected method alone brings a candidate within k of it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Methods {
+    Factors,
+    Sequence,
+    #[default]
+    Both,
+}
+
+impl Methods {
+    pub fn uses_factors(self) -> bool {
+        self != Methods::Sequence
+    }
+
+    pub fn uses_sequence(self) -> bool {
+        self != Methods::Factors
+    }
+}
+
+impl std::str::FromStr for Methods {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "factors" => Ok(Methods::Factors),
+            "sequence" => Ok(Methods::Sequence),
+            "both" => Ok(Methods::Both),
+            other => Err(Error::Parse { input: other.to_string(), expected: "factors, sequence or both" }),
+        }
+    }
+}
+
+impl std::fmt::Display for Methods {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(match self {
+            Methods::Factors => "factors",
+            Methods::Sequence => "sequence",
+            Methods::Both => "both",
+        })
+    }
+}
+
+// Which side of a candidate a prime must lie on to be covered by it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    // The prime is at or above the candidate, 0 <= prime - candidate <= k
+    Above,
+    // The prime is at or below the candidate, 0 <= candidate - prime <= k
+    Below,
+    #[default]
+    Both,
+}
+
+impl std::str::FromStr for Direction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "above" => Ok(Direction::Above),
+            "below" => Ok(Direction::Below),
+            "both" => Ok(Direction::Both),
+            other => Err(Error::Parse { input: other.to_string(), expected: "above, below or both" }),
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(match self {
+            Direction::Above => "above",
+            Direction::Below => "below",
+            Direction::Both => "both",
+        })
+    }
+}
+
+// The number whose divisors are the factor method's candidates at scale m.
+// The conjecture uses m*base itself; the others are variations to compare it
+// with, and keep the range ((m-1)*base, m*base] of the scale unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FactorBase {
+    // m*base
+    #[default]
+    Linear,
+    // m*base + c
+    Affine { c: u64 },
+    // (m*base)^2
+    Square,
+}
+
+impl FactorBase {
+    // The number whose divisors are taken at scale m
+    pub fn value(self, m: &BigUint, base: u64) -> BigUint {
+        let scaled = m * base;
+        match self {
+            FactorBase::Linear => scaled,
+            FactorBase::Affine { c } => scaled + c,
+            FactorBase::Square => &scaled * &scaled,
+        }
+    }
+
+    // Factorization of value(m, base). Linear and Square only factor m and
+    // reuse the cached factorization of the base; Affine's shifted number
+    // shares nothing with either and is factored in full.
+    pub fn factorization(self, m: &BigUint, base: u64) -> Vec<(BigUint, usize)> {
+        match self {
+            FactorBase::Linear => factorization_of_scaled_base(m, base),
+            FactorBase::Affine { .. } => factorize_biguint(&self.value(m, base)),
+            FactorBase::Square => factorization_of_scaled_base(m, base).into_iter()
+                .map(|(prime, power)| (prime, 2 * power))
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for FactorBase {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_error = || Error::Parse { input: s.to_string(), expected: "linear, affine:<c> or square" };
+        match s {
+            "linear" => Ok(FactorBase::Linear),
+            "square" => Ok(FactorBase::Square),
+            other => match other.strip_prefix("affine:") {
+                Some(c) => c.parse().map(|c| FactorBase::Affine { c }).map_err(|_| parse_error()),
+                None => Err(parse_error()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for FactorBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FactorBase::Linear => f.pad("m*base"),
+            FactorBase::Affine { c } => f.pad(&format!("m*base + {}", c)),
+            FactorBase::Square => f.pad("(m*base)^2"),
+        }
+    }
+}
+
+// A missed prime and the scale it was checked at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissedAt {
+    #[serde(serialize_with = "serde_helpers::scale")]
+    pub m: BigUint,
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub prime: BigUint,
+}
+
+// The smallest prime a fail-fast scan has missed so far. Scales checked in
+// parallel record their first miss in it and poll it to stop early, so a
+// scale only gives up once a prime below the ones it is checking was missed.
+#[derive(Debug, Default)]
+pub struct FirstMiss {
+    // Set with the first miss, so polling needs no lock until then
+    found: AtomicBool,
+    miss: Mutex<Option<MissedAt>>,
+}
+
+impl FirstMiss {
+    pub fn new() -> Self {
+        FirstMiss::default()
+    }
+
+    // Keep prime if it is smaller than every miss recorded so far
+    pub fn record(&self, m: &BigUint, prime: &BigUint) {
+        let mut miss = self.miss.lock().unwrap();
+        if miss.as_ref().is_none_or(|first| prime < &first.prime) {
+            *miss = Some(MissedAt { m: m.clone(), prime: prime.clone() });
+        }
+        self.found.store(true, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> Option<MissedAt> {
+        self.miss.lock().unwrap().clone()
+    }
+
+    // Whether a prime smaller than n was missed already
+    pub fn is_below(&self, n: &BigUint) -> bool {
+        self.found.load(Ordering::Relaxed) && self.miss.lock().unwrap().as_ref().is_some_and(|first| &first.prime < n)
+    }
+}
+
+// Settings shared by every scale of a run: how candidates are built and how
+// close a prime must be to one of them
+#[derive(Debug, Clone)]
+pub struct RangeParams {
+    pub base: u64,
+    pub max_k: u64,
+    // Drop factors sharing a prime with the base, since they can never
+    // coincide with a prime's residue class
+    pub coprime_only: bool,
+    // Keep at most this many relevant factors, those nearest the middle of the
+    // range; None keeps them all
+    pub max_factors: Option<usize>,
+    // Factors are kept within this multiple of max_k of the range. Past 1 the
+    // extra factors are too far to cover any of its primes, and only change
+    // the nearest distances reported; below 1 some that could are dropped.
+    pub candidate_window_factor: f64,
+    // The number whose divisors are the factor candidates
+    pub factor_base: FactorBase,
+    pub sequence: Sequence,
+    // Where each scale's sequence starts, past (m-1)*base; None derives it
+    // from the base with default_seq_offset
+    pub seq_offset: Option<u64>,
+    // Which of the two methods supply candidates
+    pub methods: Methods,
+    // Which side of a candidate a prime may lie on and still be covered by it
+    pub direction: Direction,
+    // Whether check_scaled_range checks its primes in parallel
+    pub parallel_mode: ParallelMode,
+    pub sampling: Sampling,
+    // Seed of the RNG placing sample blocks and picking the primes of capped
+    // ranges; the same seed checks the same numbers
+    pub sampling_seed: u64,
+    // Whether each range also includes its start
+    pub interval: Interval,
+    // Also report how twin prime pairs among the checked primes are covered
+    pub twins: bool,
+    // Also report coverage by the gap between each checked prime and the one before it
+    pub gap_analysis: bool,
+    // Ascending upper bounds of the nearest-candidate distance histogram buckets
+    pub distance_bins: Vec<u64>,
+    // Primes per parallel batch of primality and coverage checks
+    pub chunk_size: usize,
+    // Primes checked between progress updates
+    pub report_interval: usize,
+    // Cross-check the primality of the checked primes, and of a composite
+    // after each, with num_prime's strict test
+    pub verify: bool,
+    // Compare the number of primes found in each exhaustively enumerated range
+    // with li(end) - li(start)
+    pub sanity_check: bool,
+    // Wall-clock budget for checking one scale; a scale that runs past it
+    // stops early and is reported as timed out. None never stops.
+    pub scale_timeout: Option<Duration>,
+    // Stop at the first missed prime: shared by every scale of a fail-fast
+    // scan, which each stop once a smaller prime than theirs was missed
+    pub fail_fast: Option<Arc<FirstMiss>>,
+}
+
+impl RangeParams {
+    // All factors and the triangular sequence, as in the original conjecture
+    pub fn new(base: u64, max_k: u64) -> Self {
+        RangeParams {
+            base,
+            max_k,
+            coprime_only: false,
+            max_factors: None,
+            candidate_window_factor: 1.0,
+            factor_base: FactorBase::default(),
+            sequence: Sequence::default(),
+            seq_offset: None,
+            methods: Methods::default(),
+            direction: Direction::default(),
+            parallel_mode: ParallelMode::default(),
+            sampling: Sampling::default(),
+            sampling_seed: DEFAULT_SAMPLING_SEED,
+            interval: Interval::default(),
+            twins: false,
+            gap_analysis: false,
+            verify: false,
+            sanity_check: false,
+            scale_timeout: None,
+            fail_fast: None,
+            distance_bins: DISTANCE_BINS.to_vec(),
+            chunk_size: PRIME_TEST_CHUNK_SIZE,
+            report_interval: REPORT_INTERVAL,
+        }
+    }
+
+    // The sequence offset in effect, given or derived from the base
+    pub fn sequence_offset(&self) -> u64 {
+        self.seq_offset.unwrap_or_else(|| default_seq_offset(self.base))
+    }
+
+    // How far past either end of a range factors are kept: max_k times the
+    // window factor, rounded up
+    pub fn candidate_window(&self) -> u64 {
+        (self.max_k as f64 * self.candidate_window_factor).ceil() as u64
+    }
+}
+
+// Bounds of the half-open range (range_start, range_end] of scale m, i.e.
+// ((m-1)*base, m*base]: range_start itself belongs to the scale below. Scale 1
+// starts at 1 rather than 0, which excludes no prime, and scale 0 is (0, 0].
+pub fn range_bounds(m: &BigUint, base: u64) -> (BigUint, BigUint) {
+    let range_start = if m.is_zero() {
+        BigUint::zero()
+    } else if m.is_one() {
+        BigUint::one()
+    } else {
+        (m - 1u32) * base
+    };
+    (range_start, m * base)
+}
+
+// Every scale from first to last, inclusive, however large
+pub fn scale_range(first: &BigUint, last: &BigUint) -> impl Iterator<Item = BigUint> {
+    let last = last.clone();
+    std::iter::successors(Some(first.clone()), |m| Some(m + 1u32)).take_while(move |m| m <= &last)
+}
+
+// How far past the start of its range each scale's sequence starts unless told
+// otherwise: base/2 + 1, just past the middle, e.g. 181 for base 360 and 211
+// for base 420
+pub fn default_seq_offset(base: u64) -> u64 {
+    base / 2 + 1
+}
+
+// First term of scale m's sequence, just past the middle of its range:
+// (m-1)*base + base/2 + 1, e.g. 181 for m = 1 and base 360. Scale 0 has none
+// and gets 0.
+pub fn sequence_seed(m: &BigUint, base: u64) -> BigUint {
+    sequence_seed_at(m, base, default_seq_offset(base))
+}
+
+// sequence_seed with the seed seq_offset past the start of the range, (m-1)*base + seq_offset.
+// Every scale restarts its sequence from its own seed rather than continuing
+// one global sequence, so there is no shared prefix to carry from one scale to
+// the next: a scale generates only the few terms between its seed and
+// m*base + k, and with k below the offset those end before the next seed.
+pub fn sequence_seed_at(m: &BigUint, base: u64, seq_offset: u64) -> BigUint {
+    if m.is_zero() {
+        return BigUint::zero();
+    }
+    (m - 1u32) * base + seq_offset
+}
+
+// Compute the range for scale m and generate the candidates of both methods.
+// Scales start at 1; m = 0 is the empty range (0, 0] with no candidates.
+pub fn prepare_range(m: &BigUint, params: &RangeParams) -> PreparedRange {
+    prepare_range_with(m, params, true)
+}
+
+// prepare_range, optionally leaving seq_terms empty for callers that stream
+// the terms from seq_base instead
+fn prepare_range_with(m: &BigUint, params: &RangeParams, collect_terms: bool) -> PreparedRange {
+    let RangeParams { base, max_k, coprime_only, max_factors, factor_base, ref sequence, methods, .. } = *params;
+    if m.is_zero() {
+        return PreparedRange {
+            range_start: BigUint::zero(),
+            range_end: BigUint::zero(),
+            factors_base: BigUint::zero(),
+            total_factors: 0,
+            relevant_factors: Vec::new(),
+            factors_dropped: 0,
+            seq_base: BigUint::zero(),
+            seq_terms: Vec::new(),
+            factor_gen_time: Duration::ZERO,
+            seq_gen_time: Duration::ZERO,
+        };
+    }
+
+    let max_k_biguint = BigUint::from(max_k);
+
+    let (range_start, range_end) = range_bounds(m, base);
+
+    // --- Scaled Method 1 Candidates (Factors of m * base, or its variation) ---
+    let factor_start_time = Instant::now();
+    let factors_base = factor_base.value(m, base);
+    let (total_factors, mut relevant_factors) = if methods.uses_factors() {
+        let factorization = factor_base.factorization(m, base);
+        // Only factors within the candidate window of the range are enumerated;
+        // the rest of the divisors of factors_base are never built
+        let window = BigUint::from(params.candidate_window());
+        let relevant_factors = factor::divisors_in_range(
+            &factorization,
+            &range_start.clone().saturating_sub(&window),
+            &(range_end.clone() + &window),
+        );
+        (factor::divisor_count(&factorization) as usize, relevant_factors)
+    } else {
+        (0, Vec::new())
+    };
+    let modulus = BigUint::from(base);
+    relevant_factors.retain(|f| !coprime_only || f.gcd(&modulus).is_one());
+    // nearest_candidate's binary search relies on this however the divisors were found
+    relevant_factors.sort();
+    relevant_factors.dedup();
+    let factors_dropped = match max_factors {
+        Some(cap) => cap_factors(&mut relevant_factors, cap, &range_start, &range_end),
+        None => 0,
+    };
+    let factor_gen_time = factor_start_time.elapsed();
+    debug!("m={}: kept {} of {} factors of {} in {}",
+        m, relevant_factors.len(), total_factors, factors_base, format_duration(factor_gen_time));
+
+    // --- Scaled Method 2 Candidates (Recursive Sequence terms) ---
+    let seq_start_time = Instant::now();
+    let seq_base = sequence_seed_at(m, base, params.sequence_offset());
+
+    // Generate sequence terms within range
+    let seq_terms = if collect_terms && methods.uses_sequence() {
+        sequence.terms(&seq_base, &(range_end.clone() + &max_k_biguint))
+    } else {
+        Vec::new()
+    };
+    // Terms stops at the first term that doesn't grow, so even custom sequences come out ascending
+    debug_assert!(is_strictly_ascending(&seq_terms));
+    let seq_gen_time = seq_start_time.elapsed();
+    if collect_terms && methods.uses_sequence() {
+        debug!("m={}: generated {} sequence terms from {} in {}", m, seq_terms.len(), seq_base, format_duration(seq_gen_time));
+    }
+
+    PreparedRange {
+        range_start,
+        range_end,
+        factors_base,
+        total_factors,
+        relevant_factors,
+        factors_dropped,
+        seq_base,
+        seq_terms,
+        factor_gen_time,
+        seq_gen_time,
+    }
+}
+
+// Keep the cap factors nearest the midpoint of (range_start, range_end], ties
+// going to the smaller factor, still sorted ascending, and return how many were
+// dropped. Those near the middle are the ones most primes of the range are
+// nearest to, so the two ends of the range lose their factors first.
+fn cap_factors(factors: &mut Vec<BigUint>, cap: usize, range_start: &BigUint, range_end: &BigUint) -> usize {
+    if factors.len() <= cap {
+        return 0;
+    }
+    let dropped = factors.len() - cap;
+    let midpoint: BigUint = (range_start + range_end) / 2u32;
+    factors.sort_by_cached_key(|f| (abs_diff(f, &midpoint), f.clone()));
+    factors.truncate(cap);
+    factors.sort();
+    dropped
+}
+
+// Scale m whose range ((m-1)*base, m*base] contains n
+pub fn scale_of(n: &BigUint, base: u64) -> BigUint {
+    ceil_div(n, &BigUint::from(base)).max(BigUint::one())
+}
+
+// Nearest candidate of each method to one prime at one scale
+#[derive(Debug, Clone, Serialize)]
+pub struct PrimeCoverage {
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub prime: BigUint,
+    #[serde(serialize_with = "serde_helpers::scale")]
+    pub m: BigUint,
+    pub max_k: u64,
+    // Whether prime lies in the scale's own range; candidates are only
+    // generated near that range, so distances from outside it can be large
+    pub in_range: bool,
+    // (candidate, distance), or None if the method had no candidates
+    #[serde(serialize_with = "serde_helpers::nearest")]
+    pub nearest_factor: Option<(BigUint, BigUint)>,
+    #[serde(serialize_with = "serde_helpers::nearest")]
+    pub nearest_seq: Option<(BigUint, BigUint)>,
+}
+
+impl PrimeCoverage {
+    fn within_k<'a>(&self, nearest: &'a Option<(BigUint, BigUint)>) -> Option<&'a (BigUint, BigUint)> {
+        nearest.as_ref().filter(|(_, dist)| dist <= &BigUint::from(self.max_k))
+    }
+
+    // The factor within k covering the prime, with its distance
+    pub fn covering_factor(&self) -> Option<&(BigUint, BigUint)> {
+        self.within_k(&self.nearest_factor)
+    }
+
+    // The sequence term within k covering the prime, with its distance
+    pub fn covering_seq_term(&self) -> Option<&(BigUint, BigUint)> {
+        self.within_k(&self.nearest_seq)
+    }
+
+    pub fn covered_by_factor(&self) -> bool {
+        self.covering_factor().is_some()
+    }
+
+    pub fn covered_by_seq(&self) -> bool {
+        self.covering_seq_term().is_some()
+    }
+
+    pub fn is_covered(&self) -> bool {
+        self.covered_by_factor() || self.covered_by_seq()
+    }
+}
+
+// Check a single prime against the candidates of scale m, e.g. to inspect a
+// counterexample. The prime is not tested for primality.
+pub fn check_single_prime(prime: &BigUint, m: &BigUint, params: &RangeParams) -> PrimeCoverage {
+    check_prime_in_prepared_range(prime, m, &prepare_range(m, params), params)
+}
+
+// check_single_prime against candidates already prepared for scale m, so many
+// primes of one scale share a single prepare_range
+pub fn check_prime_in_prepared_range(prime: &BigUint, m: &BigUint, prepared: &PreparedRange, params: &RangeParams) -> PrimeCoverage {
+    PrimeCoverage {
+        prime: prime.clone(),
+        m: m.clone(),
+        max_k: params.max_k,
+        in_range: params.interval.contains(prime, &prepared.range_start, &prepared.range_end),
+        nearest_factor: nearest_candidate_directed(prime, &prepared.relevant_factors, params.direction),
+        nearest_seq: nearest_candidate_directed(prime, &prepared.seq_terms, params.direction),
+    }
+}
+
+// Smallest k such that every prime of the range of scale m is within k of one
+// of the candidates params builds, on the side params.direction allows; its
+// max_k is ignored. With every factor of m*base, m*base itself covers the
+// range within base, so candidates generated with k = base are enough. Other
+// settings can leave a prime with no candidate that close, and give None.
+pub fn minimal_k_for_range(m: &BigUint, params: &RangeParams) -> Option<u64> {
+    let params = RangeParams { max_k: params.base, ..params.clone() };
+    let prepared = prepare_range(m, &params);
+    let primes: Vec<BigUint> = primes_in_range_iter_with(
+        &prepared.range_start, &prepared.range_end, params.sampling, params.sampling_seed, params.interval, params.chunk_size,
+    ).collect();
+
+    let nearest = |candidates: &[BigUint], prime: &BigUint| {
+        nearest_candidate_directed(prime, candidates, params.direction).map(|(_, dist)| dist)
+    };
+    let distances: Option<Vec<BigUint>> = primes.par_iter()
+        .map(|prime| {
+            let dist = nearest(&prepared.relevant_factors, prime).into_iter()
+                .chain(nearest(&prepared.seq_terms, prime))
+                .min()?;
+            (dist <= BigUint::from(params.base)).then_some(dist)
+        })
+        .collect();
+    distances.map(|distances| distances.into_iter().max().map_or(0, |dist| dist.to_u64().expect("distance is bounded by base")))
+}
+
+// Which of the two methods a candidate comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandidateKind {
+    Factor,
+    Sequence,
+}
+
+impl std::fmt::Display for CandidateKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            CandidateKind::Factor => "factor",
+            CandidateKind::Sequence => "sequence",
+        })
+    }
+}
+
+// Which candidate sets a candidate of a merged set belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandidateSource {
+    Factor,
+    Sequence,
+    Both,
+}
+
+impl std::fmt::Display for CandidateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            CandidateSource::Factor => "factor",
+            CandidateSource::Sequence => "sequence",
+            CandidateSource::Both => "both",
+        })
+    }
+}
+
+// One candidate of scale m and how many of the scale's checked primes lie within max_k of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CandidateDensity {
+    #[serde(serialize_with = "serde_helpers::scale")]
+    pub m: BigUint,
+    pub kind: CandidateKind,
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub candidate: BigUint,
+    pub primes_within_k: usize,
+}
+
+// The proximity check turned around: for each candidate, the number of primes
+// within max_k of it. Primes must be sorted ascending; each count is two
+// binary searches for the ends of [candidate - max_k, candidate + max_k].
+pub fn primes_near_candidates(candidates: &[BigUint], primes: &[BigUint], max_k: &BigUint) -> Vec<usize> {
+    debug_assert!(is_strictly_ascending(primes));
+    candidates.iter()
+        .map(|candidate| {
+            let low = candidate.saturating_sub(max_k);
+            let high = candidate + max_k;
+            primes.partition_point(|p| p <= &high) - primes.partition_point(|p| p < &low)
+        })
+        .collect()
+}
+
+// Every candidate of scale m, factors first, with the number of the scale's
+// primes (up to max_primes_to_check of them, spread over the range) within k of it. Candidates near
+// the ends of the range have neighbours in the next scales that aren't counted.
+pub fn candidate_densities(m: &BigUint, params: &RangeParams, max_primes_to_check: usize) -> Vec<CandidateDensity> {
+    let prepared = prepare_range(m, params);
+    let (primes, _, _) = primes_to_check(&prepared.range_start, &prepared.range_end, params, max_primes_to_check);
+    let primes: Vec<BigUint> = primes.collect();
+    let max_k = BigUint::from(params.max_k);
+
+    [(CandidateKind::Factor, prepared.relevant_factors), (CandidateKind::Sequence, prepared.seq_terms)]
+        .into_iter()
+        .flat_map(|(kind, candidates)| {
+            let counts = primes_near_candidates(&candidates, &primes, &max_k);
+            candidates.into_iter().zip(counts)
+                .map(move |(candidate, primes_within_k)| CandidateDensity { m: m.clone(), kind, candidate, primes_within_k })
+        })
+        .collect()
+}
+
+// Column names of the rows written by --include-composites --format csv
+pub const DENSITY_HEADER: &str = "m,kind,candidate,primes_within_k";
+
+// Logarithmic integral li(x), by Ramanujan's series; li(x) - li(2) approximates
+// the number of primes up to x. Accurate to f64 precision while x fits in one.
+pub fn log_integral(x: f64) -> f64 {
+    const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+    let ln_x = x.ln();
+    let mut sum = 0.0;
+    let mut term = 1.0;
+    let mut odd_reciprocals = 0.0;
+    for n in 1..=1000 {
+        // term = ln(x)^n / (n! 2^(n-1)), and the inner sum runs over k <= (n-1)/2
+        term *= ln_x / n as f64;
+        if n > 1 {
+            term /= 2.0;
+        }
+        if n % 2 == 1 {
+            odd_reciprocals += 1.0 / n as f64;
+        }
+        let signed = if n % 2 == 1 { term } else { -term };
+        sum += signed * odd_reciprocals;
+        if n as f64 > ln_x && term * odd_reciprocals < f64::EPSILON * sum.abs() {
+            break;
+        }
+    }
+    EULER_GAMMA + ln_x.ln() + x.sqrt() * sum
+}
+
+// Approximate number of primes in (range_start, range_end], without testing any.
+// Once li(x) outgrows f64's 53 bits of integer precision the difference of two
+// nearby values is noise, so wider numbers use the prime density 1/ln(x) at the
+// midpoint, which is just as good for ranges that are narrow next to their start.
+pub fn approximate_prime_count(range_start: &BigUint, range_end: &BigUint) -> f64 {
+    if range_end <= range_start {
+        return 0.0;
+    }
+    let start = range_start.to_f64().unwrap_or(f64::INFINITY).max(2.0);
+    let end = range_end.to_f64().unwrap_or(f64::INFINITY);
+    if end <= 2.0 {
+        return 0.0;
+    }
+    if end < (1u64 << 53) as f64 {
+        return (log_integral(end) - log_integral(start)).max(0.0);
+    }
+    let width = (range_end - range_start).to_f64().unwrap_or(f64::INFINITY);
+    width / (start / 2.0 + end / 2.0).ln()
+}
+
+// Number of divisors of m*base, from the factorization of m merged with the
+// cached one of the base, without listing the divisors
+pub fn divisor_count_of_scaled_base(m: &BigUint, base: u64) -> u64 {
+    factor::divisor_count(&factorization_of_scaled_base(m, base))
+}
+
+// Default upper bounds (inclusive) of the nearest-candidate distance histogram
+// buckets; a final bucket collects everything beyond the last bound
+pub const DISTANCE_BINS: [u64; 4] = [10, 50, 100, 180];
+
+// Upper bounds (inclusive) of the prime gap buckets of a gap analysis; a final
+// bucket collects every larger gap
+pub const GAP_BINS: [u64; 5] = [2, 6, 12, 24, 48];
+
+// Bucket of a distance in a histogram with these ascending upper bounds
+pub fn histogram_bucket(bins: &[u64], dist: &BigUint) -> usize {
+    bins.iter()
+        .position(|bound| dist <= &BigUint::from(*bound))
+        .unwrap_or(bins.len())
+}
+
+// Bucket labels such as 0-10, 11-50 and >180 for these ascending upper bounds
+pub fn histogram_labels(bins: &[u64]) -> Vec<String> {
+    let mut lower = 0;
+    let mut labels: Vec<String> = bins.iter()
+        .map(|&upper| {
+            let label = format!("{}-{}", lower, upper);
+            lower = upper + 1;
+            label
+        })
+        .collect();
+    labels.push(match bins.last() {
+        Some(last) => format!(">{}", last),
+        None => "0+".to_string(),
+    });
+    labels
+}
+
+// A prime no candidate covered, with the closest candidate across both methods
+#[derive(Debug, Clone, Serialize)]
+pub struct MissedPrime {
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub prime: BigUint,
+    // (candidate, distance), or None if the range had no candidates at all
+    #[serde(serialize_with = "serde_helpers::nearest")]
+    pub nearest: Option<(BigUint, BigUint)>,
+}
+
+// How the twin prime pairs (p, p + 2) among a range's checked primes are
+// covered: by how many of the pair's two primes are within k of a candidate
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TwinCoverage {
+    pub pairs: usize,
+    pub both_covered: usize,
+    pub one_covered: usize,
+    pub neither_covered: usize,
+}
+
+impl TwinCoverage {
+    fn record(&mut self, first_covered: bool, second_covered: bool) {
+        self.pairs += 1;
+        match (first_covered, second_covered) {
+            (true, true) => self.both_covered += 1,
+            (false, false) => self.neither_covered += 1,
+            _ => self.one_covered += 1,
+        }
+    }
+}
+
+// How coverage of a range's checked primes varies with the gap to the prime
+// before each: per bucket of gap size, how many primes have such a gap and how
+// many of those are within k of a candidate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GapCoverage {
+    // Ascending upper bounds of the buckets, with one more bucket past the last
+    pub bins: Vec<u64>,
+    pub primes: Vec<usize>,
+    pub covered: Vec<usize>,
+}
+
+impl GapCoverage {
+    pub fn new(bins: &[u64]) -> Self {
+        GapCoverage { bins: bins.to_vec(), primes: vec![0; bins.len() + 1], covered: vec![0; bins.len() + 1] }
+    }
+
+    fn record(&mut self, gap: &BigUint, covered: bool) {
+        let bucket = histogram_bucket(&self.bins, gap);
+        self.primes[bucket] += 1;
+        if covered {
+            self.covered[bucket] += 1;
+        }
+    }
+
+    // Add the counts of another analysis with the same buckets
+    pub fn merge(&mut self, other: &GapCoverage) {
+        debug_assert_eq!(self.bins, other.bins, "gap analyses must share their buckets");
+        self.primes.iter_mut().zip(&other.primes).for_each(|(sum, count)| *sum += count);
+        self.covered.iter_mut().zip(&other.covered).for_each(|(sum, count)| *sum += count);
+    }
+
+    // Share of each bucket's primes that are covered, or None for an empty bucket
+    pub fn coverage_rates(&self) -> Vec<Option<f64>> {
+        self.covered.iter().zip(&self.primes).map(|(&covered, &primes)| coverage_percent(covered, primes)).collect()
+    }
+}
+
+// The non-empty buckets of a gap analysis, e.g. "3-6: 40/41 (97.6%)"
+pub fn format_gap_coverage(gaps: &GapCoverage) -> String {
+    let buckets: Vec<String> = histogram_labels(&gaps.bins).iter()
+        .zip(gaps.primes.iter().zip(&gaps.covered))
+        .zip(gaps.coverage_rates())
+        .filter_map(|((label, (primes, covered)), rate)| {
+            rate.map(|rate| format!("{}: {}/{} ({:.1}%)", label, covered, primes, rate))
+        })
+        .collect();
+    if buckets.is_empty() {
+        "no gaps recorded".to_string()
+    } else {
+        buckets.join(", ")
+    }
+}
+
+// A number the fast primality path and the reference test disagree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrimalityDisagreement {
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub number: BigUint,
+    // Whether the fast path took it for a prime; the reference test says otherwise
+    pub listed_as_prime: bool,
+}
+
+// How far the primes found in a range may stray from li(end) - li(start), in
+// standard deviations of a Poisson count with that mean, before the sanity
+// check flags it. Base-wide scales stay within 2.5 of them up to m = 10^7.
+pub const SANITY_CHECK_SIGMAS: f64 = 5.0;
+
+// Primes found in a range against the estimate li(end) - li(start) of the
+// prime-counting function, a cheap guard against a primality test that drops
+// or invents primes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrimeCountCheck {
+    pub found: usize,
+    pub expected: f64,
+    // found - expected, in units of sqrt(expected)
+    pub deviation: f64,
+    pub within_tolerance: bool,
+}
+
+impl PrimeCountCheck {
+    pub fn new(found: usize, range_start: &BigUint, range_end: &BigUint) -> Self {
+        let expected = approximate_prime_count(range_start, range_end);
+        let deviation = (found as f64 - expected) / expected.sqrt().max(1.0);
+        PrimeCountCheck { found, expected, deviation, within_tolerance: deviation.abs() <= SANITY_CHECK_SIGMAS }
+    }
+}
+
+// Outcome of cross-checking a scale's primality results with is_prime_reference
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PrimalityVerification {
+    pub primes_verified: usize,
+    pub composites_verified: usize,
+    pub disagreements: Vec<PrimalityDisagreement>,
+}
+
+impl PrimalityVerification {
+    // Check every prime of a chunk, and the odd number after it whenever that
+    // comes before the next prime and so was passed over as composite. Sampled
+    // ranges skip numbers, so there the composite must also be one the fast
+    // path rejects itself. The last prime of a chunk has no composite checked.
+    fn record(&mut self, m: &BigUint, chunk: &[BigUint], sampled: bool, parallel_mode: ParallelMode) {
+        let two = BigUint::from(2u32);
+        let check = |i: usize| {
+            let prime = &chunk[i];
+            let composite = chunk.get(i + 1)
+                .map(|next| (prime + &two, next))
+                .filter(|(composite, next)| composite < *next && (!sampled || !is_prime_biguint(composite)))
+                .map(|(composite, _)| composite);
+            let prime_wrong = !is_prime_reference(prime);
+            let composite_wrong = composite.as_ref().is_some_and(is_prime_reference);
+            (composite.is_some(), prime_wrong.then(|| prime.clone()), composite.filter(|_| composite_wrong))
+        };
+        let outcomes: Vec<_> = match parallel_mode {
+            ParallelMode::Primes => (0..chunk.len()).into_par_iter().map(check).collect(),
+            ParallelMode::Scales => (0..chunk.len()).map(check).collect(),
+        };
+
+        self.primes_verified += chunk.len();
+        for (composite_checked, wrong_prime, wrong_composite) in outcomes {
+            self.composites_verified += composite_checked as usize;
+            if let Some(number) = wrong_prime {
+                error!("m={}: {} was found prime but num_prime says it is composite", m, number);
+                self.disagreements.push(PrimalityDisagreement { number, listed_as_prime: true });
+            }
+            if let Some(number) = wrong_composite {
+                error!("m={}: {} was passed over as composite but num_prime says it is prime", m, number);
+                self.disagreements.push(PrimalityDisagreement { number, listed_as_prime: false });
+            }
+        }
+    }
+}
+
+// Distance from one checked prime to the nearest candidate of each method,
+// or None if that method had no candidates
+#[derive(Debug, Clone)]
+pub struct PrimeAttribution {
+    pub prime: BigUint,
+    // (candidate, distance) of the nearest factor and sequence term
+    pub nearest_factor: Option<(BigUint, BigUint)>,
+    pub nearest_seq: Option<(BigUint, BigUint)>,
+}
+
+// Time a range check spent in each of its phases. Primes are streamed and
+// checked a chunk at a time, so prime generation, sequence generation and the
+// proximity checks interleave and each is summed over every chunk.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimings {
+    pub prime_gen: Duration,
+    pub factor_gen: Duration,
+    pub seq_gen: Duration,
+    pub proximity: Duration,
+}
+
+// Serialized as prime_gen_secs, prime_gen_nanos and so on
+impl Serialize for PhaseTimings {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serde_helpers::timings(&[
+            ("prime_gen", self.prime_gen),
+            ("factor_gen", self.factor_gen),
+            ("seq_gen", self.seq_gen),
+            ("proximity", self.proximity),
+        ], serializer)
+    }
+}
+
+// How many covered primes lie above, below or on the nearest candidate covering them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OffsetSides {
+    pub above: usize,
+    pub below: usize,
+    pub exact: usize,
+}
+
+impl OffsetSides {
+    fn record(&mut self, prime: &BigUint, candidate: &BigUint) {
+        match prime.cmp(candidate) {
+            std::cmp::Ordering::Greater => self.above += 1,
+            std::cmp::Ordering::Less => self.below += 1,
+            std::cmp::Ordering::Equal => self.exact += 1,
+        }
+    }
+}
+
+// Version of the JSON output schema, written as the top-level schema_version
+// of every `--format json` document and of every `--format jsonl` line. It is
+// bumped whenever a field is removed or renamed or changes meaning; adding a
+// field leaves it alone, so parsers should ignore keys they don't know.
+//
+// Schema 1:
+// - json: {schema_version, reports: [RangeReport], summary: RunSummary}, or with
+//   a ranges file {schema_version, ranges: [{min_m, max_m, reports}], summary}
+// - jsonl: {schema_version, ...RangeReport} per scale, then {schema_version, summary}
+// - RangeReport and RunSummary fields keep the names they have below. BigUints
+//   are decimal strings, scales are numbers while they fit in u64 and strings
+//   past it, and each duration is a <name>_secs and <name>_nanos pair.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+// Outcome of checking one scale, as produced by check_scaled_range
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeReport {
+    #[serde(serialize_with = "serde_helpers::scale")]
+    pub m: BigUint,
+    pub base: u64,
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub range_start: BigUint,
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub range_end: BigUint,
+    // Whether range_start was in the range too
+    pub interval: Interval,
+    pub max_k: u64,
+    pub coprime_only: bool,
+    // The mode that was asked for, and whether it made the range's primes only
+    // a sample of it spread over the range, so coverage was not checked exhaustively
+    pub sampling: Sampling,
+    pub sampled: bool,
+    // Whether the primes were enumerated, sampled or found not to exist
+    pub prime_gen_outcome: PrimeGenOutcome,
+    // Primes in the range before the max_primes_to_check cap
+    pub primes_in_range: usize,
+    pub total_checked: usize,
+    // Checked primes whose primality is proven, and those beyond u64 only
+    // known to be probable primes at the configured certainty. Primes given
+    // to check_primes_against_range are not tested and count in neither.
+    pub proven_primes: usize,
+    pub probable_primes: usize,
+    // The number whose divisors were the factor candidates, and the
+    // expression in m and base it was computed from
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub factors_base: BigUint,
+    pub factor_base: FactorBase,
+    pub relevant_factors: usize,
+    // The factor cap, and how many relevant factors it left out; primes near
+    // only those count as missed, so a capped scale may understate coverage
+    pub max_factors: Option<usize>,
+    pub factors_dropped: usize,
+    // Factors were kept this many times max_k past either end of the range
+    pub candidate_window_factor: f64,
+    #[serde(serialize_with = "serde_helpers::biguint")]
+    pub seq_base: BigUint,
+    // Step rule of the sequence, as shown in Seq(seq_base, sequence)
+    pub sequence: String,
+    pub seq_terms: usize,
+    // The methods supplying candidates; with only one, every covered prime is
+    // counted under it alone
+    pub methods: Methods,
+    // The side of its candidates a prime had to lie on, and the side covered
+    // primes lie on relative to the nearest candidate covering them
+    pub direction: Direction,
+    pub offsets: OffsetSides,
+    // Primes within max_k of a factor but no sequence term, of a sequence
+    // term but no factor, and of both; every checked prime is in at most one
+    pub factors_only: usize,
+    pub seq_only: usize,
+    pub both_found: usize,
+    pub factors_exact: usize,
+    pub seq_exact: usize,
+    pub missed: Vec<MissedPrime>,
+    // Share of checked primes within k of a candidate, or None if none were checked
+    pub coverage_percent: Option<f64>,
+    // Every checked prime with both method distances, in ascending order.
+    // Left out of JSON output, which would otherwise list every prime.
+    #[serde(skip)]
+    pub attribution: Vec<PrimeAttribution>,
+    // Smallest k covering every checked prime, with the prime that needs it
+    #[serde(serialize_with = "serde_helpers::minimal_k")]
+    pub minimal_k: Option<(BigUint, BigUint)>,
+    // Upper bounds of the histogram buckets, and the count of primes per bucket
+    // of nearest-candidate distance with one more for distances past the last bound
+    pub distance_bins: Vec<u64>,
+    pub distance_histogram: Vec<usize>,
+    // Only analysed when RangeParams::twins is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twins: Option<TwinCoverage>,
+    // Only analysed when RangeParams::gap_analysis is set, with the GAP_BINS buckets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gaps: Option<GapCoverage>,
+    // Only cross-checked when RangeParams::verify is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<PrimalityVerification>,
+    // Only compared when RangeParams::sanity_check is set and the range was
+    // enumerated rather than sampled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prime_count_check: Option<PrimeCountCheck>,
+    // Whether the scale ran past RangeParams::scale_timeout. Its counts then
+    // cover only the chunks of primes checked in full before the budget ran out.
+    pub timed_out: bool,
+    // Whether a fail-fast scan stopped the scale at a missed prime, its own or
+    // a smaller one of another scale; its counts stop at the same chunk border
+    pub stopped_early: bool,
+    #[serde(flatten, serialize_with = "serde_helpers::elapsed")]
+    pub elapsed: Duration,
+    // Where the elapsed time went; primality cross-checks are not in any phase
+    pub phases: PhaseTimings,
+}
+
+impl RangeReport {
+    // The checked range written with its brackets, as Interval::format_range writes it
+    pub fn format_range(&self) -> String {
+        self.interval.format_range(&self.range_start, &self.range_end)
+    }
+}
+
+// A bar for one scale, updated in place alongside the other scales of its batch
+fn scale_progress_bar(progress: &MultiProgress, m: &BigUint) -> ProgressBar {
+    let style = ProgressStyle::with_template(
+        "{prefix:>14} [{bar:40}] {pos}/{len} ({percent}%) {per_sec} ETA {eta} {msg}"
+    ).expect("progress template is valid");
+    let bar = progress.add(ProgressBar::new(0));
+    bar.set_style(style.progress_chars("=> "));
+    bar.set_prefix(format!("m={}", m));
+    bar.set_message("generating primes");
+    bar
+}
+
+// Check every prime in ((m-1)*base, m*base] against both candidate sets.
+// Nothing is printed; when progress is given, a bar for this scale is added
+// to it and cleared again once the scale is done. Use print_report for the results.
+pub fn check_scaled_range(
+    m: &BigUint,
+    params: &RangeParams,
+    max_primes_to_check: usize,
+    progress: Option<&MultiProgress>,
+) -> RangeReport {
+    check_range_primes(m, params, PrimeSource::Range { max_primes_to_check }, progress)
+}
+
+// check_scaled_range with primes from elsewhere, e.g. a list computed by
+// another tool, instead of ones generated for the range. They are checked in
+// ascending order without duplicates, and all count as found in the range;
+// primes outside it are still measured against the range's own candidates.
+// Neither sampling nor a prime cap applies, and primality is not tested.
+pub fn check_primes_against_range(primes: &[BigUint], m: &BigUint, params: &RangeParams) -> RangeReport {
+    let mut primes = primes.to_vec();
+    primes.sort();
+    primes.dedup();
+    check_range_primes(m, params, PrimeSource::Given(&primes), None)
+}
+
+// Every scale from min_m to max_m checked with the default settings for base
+// and k, as an iterator of their reports in ascending m. See ScaleScan.
+pub fn scan_scales(min_m: &BigUint, max_m: &BigUint, base: u64, max_k: u64) -> ScaleScan<'static> {
+    ScaleScan::new(min_m, max_m, RangeParams::new(base, max_k), DEFAULT_MAX_PRIMES)
+}
+
+// The scales of a ScaleScan checked together, first_m to last_m, and the
+// reports of those checked in ascending m
+#[derive(Debug, Clone)]
+pub struct ScanBatch {
+    pub first_m: BigUint,
+    pub last_m: BigUint,
+    pub reports: Vec<RangeReport>,
+    // Whether the stop flag was set during the batch. Its reports then end
+    // below the first scale it skipped, and the scan ends with it.
+    pub stopped: bool,
+}
+
+// Consecutive scales checked as check_scaled_range checks them, a batch at a
+// time: with ParallelMode::Scales the scales of a batch are checked in
+// parallel, otherwise one after another. As an iterator it yields each report
+// in ascending m once its batch is done; next_batch hands over whole batches.
+// A fail-fast scan skips the scales lying wholly above a missed prime and ends
+// with the batch of the miss.
+pub struct ScaleScan<'a> {
+    params: RangeParams,
+    max_primes_to_check: usize,
+    next_m: Option<BigUint>,
+    max_m: BigUint,
+    batch_len: u64,
+    skip: HashSet<BigUint>,
+    stop: Option<&'a AtomicBool>,
+    progress: Option<&'a MultiProgress>,
+    // Reports of the last batch not yet yielded
+    pending: VecDeque<RangeReport>,
+}
+
+impl<'a> ScaleScan<'a> {
+    pub fn new(min_m: &BigUint, max_m: &BigUint, params: RangeParams, max_primes_to_check: usize) -> Self {
+        let batch_len = match params.parallel_mode {
+            ParallelMode::Scales => SCALE_BATCH_LEN.max(rayon::current_num_threads() as u64),
+            ParallelMode::Primes => SCALE_BATCH_LEN,
+        };
+        ScaleScan {
+            params,
+            max_primes_to_check,
+            next_m: Some(min_m.clone()).filter(|m| m <= max_m),
+            max_m: max_m.clone(),
+            batch_len,
+            skip: HashSet::new(),
+            stop: None,
+            progress: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // Check this many scales per batch instead, at least one
+    pub fn with_batch_len(self, batch_len: u64) -> Self {
+        ScaleScan { batch_len: batch_len.max(1), ..self }
+    }
+
+    // Leave out these scales; they get no report, and take no place in a batch's order
+    pub fn skipping(self, skip: HashSet<BigUint>) -> Self {
+        ScaleScan { skip, ..self }
+    }
+
+    // Stop once stop is set, e.g. from a signal handler: no further scale is
+    // started, and the scan ends with the batch it was set in
+    pub fn stopping_on(self, stop: &'a AtomicBool) -> Self {
+        ScaleScan { stop: Some(stop), ..self }
+    }
+
+    // Draw a progress bar for each scale while it is checked
+    pub fn with_progress(self, progress: &'a MultiProgress) -> Self {
+        ScaleScan { progress: Some(progress), ..self }
+    }
+
+    pub fn params(&self) -> &RangeParams {
+        &self.params
+    }
+
+    // The scales of the batch next_batch checks next, or None once the scan is over
+    pub fn upcoming_batch(&self) -> Option<(BigUint, BigUint)> {
+        let first_m = self.next_m.clone()?;
+        let last_m = (&first_m + (self.batch_len - 1)).min(self.max_m.clone());
+        Some((first_m, last_m))
+    }
+
+    // Check the next batch, or return None once the scan is over
+    pub fn next_batch(&mut self) -> Option<ScanBatch> {
+        self.next_batch_with(|_, _| {})
+    }
+
+    // next_batch, also calling on_scale as each scale of the batch is done,
+    // with its index among the batch's scales not skipped and its report;
+    // None for a scale not started because of a miss below it or the stop
+    // flag. Scales checked in parallel call it in any order.
+    pub fn next_batch_with(&mut self, on_scale: impl Fn(usize, Option<&RangeReport>) + Sync) -> Option<ScanBatch> {
+        let (first_m, last_m) = self.upcoming_batch()?;
+        let params = &self.params;
+        let stopped = || self.stop.is_some_and(|stop| stop.load(Ordering::SeqCst));
+
+        let check = |(index, m): (usize, BigUint)| {
+            let (range_start, _) = range_bounds(&m, params.base);
+            if stopped() || params.fail_fast.as_ref().is_some_and(|first| first.is_below(&range_start)) {
+                on_scale(index, None);
+                return None;
+            }
+            let report = check_scaled_range(&m, params, self.max_primes_to_check, self.progress);
+            on_scale(index, Some(&report));
+            Some(report)
+        };
+        let scales = scale_range(&first_m, &last_m).filter(|m| !self.skip.contains(m)).enumerate();
+        let checked: Vec<Option<RangeReport>> = match params.parallel_mode {
+            ParallelMode::Scales => scales.collect::<Vec<_>>().into_par_iter().map(check).collect(),
+            ParallelMode::Primes => scales.map(check).collect(),
+        };
+
+        // A stopped batch keeps only the scales below the first one it skipped,
+        // so whatever follows on from its last report leaves no gap
+        let stopped = stopped();
+        let reports: Vec<RangeReport> = if stopped {
+            checked.into_iter().map_while(|report| report).collect()
+        } else {
+            checked.into_iter().flatten().collect()
+        };
+
+        let missed = params.fail_fast.as_ref().is_some_and(|first| first.get().is_some());
+        self.next_m = Some(&last_m + 1u32).filter(|m| m <= &self.max_m && !missed && !stopped);
+        Some(ScanBatch { first_m, last_m, reports, stopped })
+    }
+}
+
+impl Iterator for ScaleScan<'_> {
+    type Item = RangeReport;
+
+    fn next(&mut self) -> Option<RangeReport> {
+        // A batch of skipped scales has no reports, so go on to the next one
+        while self.pending.is_empty() {
+            let batch = self.next_batch()?;
+            self.pending.extend(batch.reports);
+        }
+        self.pending.pop_front()
+    }
+}
+
+// Where check_range_primes takes its primes from
+enum PrimeSource<'a> {
+    // Generated for the range, as primes_to_check streams them
+    Range { max_primes_to_check: usize },
+    // Given in ascending order without duplicates
+    Given(&'a [BigUint]),
+}
+
+fn check_range_primes(
+    m: &BigUint,
+    params: &RangeParams,
+    source: PrimeSource<'_>,
+    progress: Option<&MultiProgress>,
+) -> RangeReport {
+    let start_time = Instant::now();
+    let RangeParams {
+        base, max_k, coprime_only, max_factors, candidate_window_factor, factor_base, ref sequence, methods, direction,
+        parallel_mode, sampling, interval, twins, gap_analysis, ref distance_bins, chunk_size, report_interval, verify, sanity_check, scale_timeout, ref fail_fast,
+        // Applied by prepare_range_with, which places the sequence seed
+        seq_offset: _,
+        // Applied by primes_to_check, which places the sample
+        sampling_seed: _,
+    } = *params;
+    let (chunk_size, report_interval) = (chunk_size.max(1), report_interval.max(1));
+    let max_k_biguint = BigUint::from(max_k);
+
+    let PreparedRange {
+        range_start,
+        range_end,
+        factors_base,
+        relevant_factors,
+        factors_dropped,
+        seq_base,
+        factor_gen_time,
+        seq_gen_time,
+        ..
+    } = prepare_range_with(m, params, false);
+    let mut phases = PhaseTimings { factor_gen: factor_gen_time, seq_gen: seq_gen_time, ..PhaseTimings::default() };
+    // Sequence terms are streamed a chunk's span at a time rather than collected.
+    // Scale 0 has no candidates, nor has a check without the sequence method,
+    // so their bound lies below any term.
+    let seq_limit = if m.is_zero() || !methods.uses_sequence() { None } else { Some(&range_end + &max_k_biguint) };
+    let mut seq_window = TermWindow::new(match &seq_limit {
+        Some(limit) => sequence.terms_iter(&seq_base, limit),
+        None => sequence.terms_iter(&BigUint::one(), &BigUint::zero()),
+    });
+
+    let bar = progress.map(|progress| scale_progress_bar(progress, m));
+
+    // --- Stream the Primes in the Range ---
+    let prime_start_time = Instant::now();
+    let tested = matches!(source, PrimeSource::Range { .. });
+    let (mut primes, primes_in_range, sampled, max_primes_to_check) = match source {
+        PrimeSource::Range { max_primes_to_check } => {
+            let (primes, primes_in_range, sampled) = primes_to_check(&range_start, &range_end, params, max_primes_to_check);
+            (primes, primes_in_range, sampled, max_primes_to_check)
+        }
+        PrimeSource::Given(primes) => {
+            let primes: Box<dyn Iterator<Item = BigUint> + Send + '_> = Box::new(primes.iter().cloned());
+            (primes, None, false, usize::MAX)
+        }
+    };
+    phases.prime_gen += prime_start_time.elapsed();
+
+    let mut report = RangeReport {
+        m: m.clone(),
+        base,
+        range_start,
+        range_end,
+        interval,
+        max_k,
+        coprime_only,
+        sampling,
+        sampled,
+        prime_gen_outcome: PrimeGenOutcome::Exhaustive,
+        primes_in_range: 0,
+        total_checked: 0,
+        proven_primes: 0,
+        probable_primes: 0,
+        factors_base,
+        factor_base,
+        relevant_factors: relevant_factors.len(),
+        max_factors,
+        factors_dropped,
+        candidate_window_factor,
+        seq_base,
+        sequence: sequence.to_string(),
+        seq_terms: 0,
+        methods,
+        direction,
+        offsets: OffsetSides::default(),
+        factors_only: 0,
+        seq_only: 0,
+        both_found: 0,
+        factors_exact: 0,
+        seq_exact: 0,
+        missed: Vec::new(),
+        coverage_percent: None,
+        attribution: Vec::new(),
+        minimal_k: None,
+        distance_bins: distance_bins.clone(),
+        distance_histogram: vec![0; distance_bins.len() + 1],
+        twins: twins.then(TwinCoverage::default),
+        gaps: gap_analysis.then(|| GapCoverage::new(&GAP_BINS)),
+        verification: verify.then(PrimalityVerification::default),
+        prime_count_check: None,
+        timed_out: false,
+        stopped_early: false,
+        elapsed: Duration::ZERO,
+        phases: PhaseTimings::default(),
+    };
+    
+    // --- Check Coverage a Chunk at a Time with Progress Tracking ---
+    if let Some(bar) = &bar {
+        bar.set_message(if sampled { "checking primes (sampled)" } else { "checking primes" });
+    }
+
+    let within = |nearest: &Option<(BigUint, BigUint)>, k: &BigUint| {
+        nearest.as_ref().is_some_and(|(_, dist)| dist <= k)
+    };
+    let zero = BigUint::zero();
+    // The last prime checked and whether it was covered, carried across chunks
+    // so twin pairs and gaps split between two chunks are still seen
+    let mut previous: Option<(BigUint, bool)> = None;
+    // Whether the checked primes are every prime of the range, so that each
+    // one's gap is to the prime checked before it
+    let consecutive = tested && !sampled && primes_in_range.is_none_or(|total| total <= max_primes_to_check);
+    // Every prime check polls the clock against the budget, and the first to
+    // find it spent raises the flag so the others of its chunk stop at once
+    let deadline = scale_timeout.map(|timeout| start_time + timeout);
+    // Checking primes sequentially sweeps the factors once across every chunk,
+    // since the primes arrive in ascending order
+    let mut factor_sweep = CandidateSweep::new(&relevant_factors);
+    let timed_out = AtomicBool::new(false);
+    let out_of_time = || {
+        if timed_out.load(Ordering::Relaxed) {
+            return true;
+        }
+        let expired = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if expired {
+            timed_out.store(true, Ordering::Relaxed);
+        }
+        expired
+    };
+    let beaten = |prime: &BigUint| fail_fast.as_ref().is_some_and(|first| first.is_below(prime));
+
+    // Only one chunk of primes is held at a time; the bar grows as chunks arrive
+    loop {
+        if out_of_time() {
+            break;
+        }
+        let remaining = max_primes_to_check - report.total_checked;
+        let prime_start_time = Instant::now();
+        let chunk: Vec<BigUint> = primes.by_ref().take(remaining.min(chunk_size)).collect();
+        phases.prime_gen += prime_start_time.elapsed();
+        if chunk.is_empty() {
+            break;
+        }
+        if beaten(&chunk[0]) {
+            report.stopped_early = true;
+            break;
+        }
+        if let Some(bar) = &bar {
+            bar.inc_length(chunk.len() as u64);
+        }
+        if let Some(verification) = report.verification.as_mut() {
+            verification.record(m, &chunk, sampled, parallel_mode);
+        }
+
+        // Only the terms around this chunk's primes can be nearest to one of them
+        let seq_start_time = Instant::now();
+        let seq_terms = seq_window.covering(&chunk[0], &chunk[chunk.len() - 1]);
+        phases.seq_gen += seq_start_time.elapsed();
+        let proximity_start_time = Instant::now();
+
+        // Find the nearest candidate of each method for every prime, reporting
+        // progress once per report_interval primes rather than after each one.
+        // Returns false once the time budget is spent or a smaller prime was missed.
+        let done = report.total_checked;
+        let tick = |idx: usize, prime: &BigUint| {
+            if out_of_time() || beaten(prime) {
+                return false;
+            }
+            let checked = done + idx + 1;
+            if checked.is_multiple_of(report_interval) {
+                if let Some(bar) = &bar {
+                    bar.inc(report_interval as u64);
+                }
+                trace!("m={}: checking prime {} ({} checked)", m, prime, checked);
+            }
+            true
+        };
+        // Parallel checks binary search each prime's candidates on its own,
+        // while sequential ones sweep them together with the ascending primes
+        let outcomes: Option<Vec<_>> = match parallel_mode {
+            ParallelMode::Primes => chunk.par_iter().enumerate()
+                .map(|(idx, prime)| tick(idx, prime).then(|| (
+                    nearest_candidate_directed(prime, &relevant_factors, direction),
+                    nearest_candidate_directed(prime, &seq_terms, direction),
+                )))
+                .collect(),
+            ParallelMode::Scales => {
+                let mut seq_sweep = CandidateSweep::new(&seq_terms);
+                chunk.iter().enumerate()
+                    .map(|(idx, prime)| tick(idx, prime).then(|| (
+                        factor_sweep.nearest(prime, direction),
+                        seq_sweep.nearest(prime, direction),
+                    )))
+                    .collect()
+            }
+        };
+        // A chunk cut short by the budget is dropped whole, so the counts
+        // never mix in an arbitrary subset of its primes
+        let Some(outcomes) = outcomes else {
+            phases.proximity += proximity_start_time.elapsed();
+            report.stopped_early = !timed_out.load(Ordering::Relaxed);
+            break;
+        };
+        report.total_checked += chunk.len();
+        if tested {
+            // Primes stream in ascending order, so the proven ones come first
+            let proven = chunk.partition_point(is_primality_proven);
+            report.proven_primes += proven;
+            report.probable_primes += chunk.len() - proven;
+        }
+        // Account for the primes since the last multiple of the interval
+        if let Some(bar) = &bar {
+            bar.set_position(report.total_checked as u64);
+        }
+
+        for (prime, (nearest_factor, nearest_seq)) in chunk.iter().zip(outcomes) {
+            // Record distance-0 coverage separately for each method
+            if within(&nearest_factor, &zero) {
+                report.factors_exact += 1;
+            }
+            if within(&nearest_seq, &zero) {
+                report.seq_exact += 1;
+            }
+
+            report.attribution.push(PrimeAttribution {
+                prime: prime.clone(),
+                nearest_factor: nearest_factor.clone(),
+                nearest_seq: nearest_seq.clone(),
+            });
+
+            // Both methods are checked for every prime so overlap is attributed
+            let factor_covered = within(&nearest_factor, &max_k_biguint);
+            let seq_covered = within(&nearest_seq, &max_k_biguint);
+            match (factor_covered, seq_covered) {
+                (true, true) => report.both_found += 1,
+                (true, false) => report.factors_only += 1,
+                (false, true) => report.seq_only += 1,
+                (false, false) => {}
+            }
+
+            let nearest = closer(nearest_factor, nearest_seq);
+            if let Some((candidate, dist)) = &nearest {
+                if factor_covered || seq_covered {
+                    report.offsets.record(prime, candidate);
+                }
+                report.distance_histogram[histogram_bucket(distance_bins, dist)] += 1;
+
+                // Keep the largest distance, preferring the smaller prime on ties
+                if report.minimal_k.as_ref().is_none_or(|(worst, _)| dist > worst) {
+                    report.minimal_k = Some((dist.clone(), prime.clone()));
+                }
+            }
+
+            // Prime not covered by either method
+            if !factor_covered && !seq_covered {
+                report.missed.push(MissedPrime { prime: prime.clone(), nearest });
+            }
+
+            let covered = factor_covered || seq_covered;
+            if let Some(twins) = report.twins.as_mut() {
+                if let Some((last, last_covered)) = &previous {
+                    if prime - last == BigUint::from(2u32) {
+                        twins.record(*last_covered, covered);
+                    }
+                }
+            }
+            if let Some(gaps) = report.gaps.as_mut() {
+                // The first prime of the range, and every prime of a sample,
+                // has its previous prime searched for instead
+                let last = match &previous {
+                    Some((last, _)) if consecutive => Some(last.clone()),
+                    _ => previous_prime(prime),
+                };
+                if let Some(last) = last {
+                    gaps.record(&(prime - last), covered);
+                }
+            }
+            if twins || gap_analysis {
+                previous = Some((prime.clone(), covered));
+            }
+        }
+        phases.proximity += proximity_start_time.elapsed();
+
+        // Misses come in ascending order, so the first is the scale's smallest.
+        // The scale only counts as stopped early if it had primes left.
+        if let (Some(first), Some(missed)) = (fail_fast, report.missed.first()) {
+            first.record(m, &missed.prime);
+            report.stopped_early = report.total_checked < max_primes_to_check && primes.next().is_some();
+            break;
+        }
+    }
+
+    // A capped range was counted up front; otherwise every prime was checked
+    report.primes_in_range = primes_in_range.unwrap_or(report.total_checked);
+    report.timed_out = timed_out.into_inner();
+    // A range is only known to be prime-free once it was enumerated to its end
+    report.prime_gen_outcome = if sampled {
+        PrimeGenOutcome::Sampled { n: sampling.sample_size() }
+    } else if report.primes_in_range == 0 && !report.timed_out && !report.stopped_early {
+        PrimeGenOutcome::Empty
+    } else {
+        PrimeGenOutcome::Exhaustive
+    };
+    match report.prime_gen_outcome {
+        PrimeGenOutcome::Empty => warn!("m={}: no primes in {}", m, report.format_range()),
+        PrimeGenOutcome::Sampled { n } if report.total_checked == 0 => {
+            warn!("m={}: none of the {} odd numbers sampled from {} is prime", m, n, report.format_range())
+        }
+        _ => {}
+    }
+    if report.timed_out {
+        warn!("m={}: timed out after {} with {} primes checked", m, format_duration(start_time.elapsed()), report.total_checked);
+    }
+    // A timed-out range stopped counting its primes partway through
+    if sanity_check && !sampled && !report.timed_out {
+        let check = PrimeCountCheck::new(report.primes_in_range, &report.range_start, &report.range_end);
+        if !check.within_tolerance {
+            warn!("m={}: found {} primes in {} where li predicts {:.1}; the primality test may be dropping primes",
+                m, check.found, report.format_range(), check.expected);
+        }
+        report.prime_count_check = Some(check);
+    }
+    report.seq_terms = seq_window.finish();
+    if let Some(bar) = &bar {
+        bar.finish_and_clear();
+    }
+    debug!("m={}: checked {} of {} primes in {}{}", m, report.total_checked, report.primes_in_range,
+        report.format_range(), if sampled { " (sampled)" } else { "" });
+
+    report.coverage_percent = coverage_percent(report.total_checked - report.missed.len(), report.total_checked);
+    report.elapsed = start_time.elapsed();
+    report.phases = phases;
+    info!("m={}: {} of {} primes covered with k={}, {} missed in {}", m,
+        report.total_checked - report.missed.len(), report.total_checked, max_k, report.missed.len(), format_duration(report.elapsed));
+    report
+}
+
+// Column names for csv_row, written once at the start of a CSV run
+pub const CSV_HEADER: &str =
+    "m,range_start,range_end,primes_checked,factors_only,seq_only,both_found,missed_count,coverage_percent,elapsed_secs,sampled";
+
+// One CSV line (without a trailing newline) per scale
+pub fn csv_row(report: &RangeReport) -> String {
+    format!("{},{},{},{},{},{},{},{},{},{:.6},{}",
+        report.m,
+        report.range_start,
+        report.range_end,
+        report.total_checked,
+        report.factors_only,
+        report.seq_only,
+        report.both_found,
+        report.missed.len(),
+        report.coverage_percent.map_or(String::new(), |percent| format!("{:.4}", percent)),
+        report.elapsed.as_secs_f64(),
+        report.sampled)
+}
+
+// Column names for write_missed, written when starting a new missed-primes file
+pub const MISSED_HEADER: &str = "m,prime,nearest_candidate,distance";
+
+// Append one line per missed prime with its scale and nearest candidate
+pub fn write_missed<W: Write>(writer: &mut W, report: &RangeReport) -> Result<()> {
+    for missed in &report.missed {
+        match &missed.nearest {
+            Some((candidate, dist)) => writeln!(writer, "{},{},{},{}", report.m, missed.prime, candidate, dist)?,
+            None => writeln!(writer, "{},{},,", report.m, missed.prime)?,
+        }
+    }
+    Ok(())
+}
+
+// Column names for write_attribution, written when starting a new attribution file
+pub const ATTRIBUTION_HEADER: &str =
+    "m,prime,nearest_factor,factor_distance,covered_by_factor,nearest_seq_term,seq_distance,covered_by_seq";
+
+// Append one line per checked prime with each method's nearest candidate, its distance and
+// whether it is within max_k. A method without candidates leaves both empty and is never covering.
+pub fn write_attribution<W: Write>(writer: &mut W, report: &RangeReport) -> Result<()> {
+    let max_k = BigUint::from(report.max_k);
+    let column = |nearest: &Option<(BigUint, BigUint)>| match nearest {
+        Some((candidate, dist)) => format!("{},{},{}", candidate, dist, dist <= &max_k),
+        None => ",,false".to_string(),
+    };
+    for prime in &report.attribution {
+        writeln!(writer, "{},{},{},{}", report.m, prime.prime,
+            column(&prime.nearest_factor), column(&prime.nearest_seq))?;
+    }
+    Ok(())
+}
+
+// Column names for write_candidates, written when starting a new candidates file
+pub const CANDIDATES_HEADER: &str = "m,kind,candidate";
+
+// Append one line per candidate of a prepared scale: every relevant factor,
+// then every sequence term, each in ascending order. A number that is both
+// gets one line of each kind.
+pub fn write_candidates<W: Write>(writer: &mut W, m: &BigUint, prepared: &PreparedRange) -> Result<()> {
+    let kinds = [(CandidateKind::Factor, &prepared.relevant_factors), (CandidateKind::Sequence, &prepared.seq_terms)];
+    for (kind, candidates) in kinds {
+        for candidate in candidates {
+            writeln!(writer, "{},{},{}", m, kind, candidate)?;
+        }
+    }
+    Ok(())
+}
+
+// Human-readable summary of one scale, printed with a single call so that
+// reports of scales checked in parallel never interleave
+pub fn print_report(report: &RangeReport) {
+    print!("{}", format_report(report));
+}
+
+// The summary print_report prints, as one string
+pub fn format_report(report: &RangeReport) -> String {
+    let mut out = String::new();
+    write_report(&mut out, report).expect("formatting into a String cannot fail");
+    out
+}
+
+fn write_report(out: &mut String, report: &RangeReport) -> std::fmt::Result {
+    use std::fmt::Write as _;
+
+    writeln!(out,
+        "\n--- Results for Range {} (Scale m={}) ---",
+        report.format_range(), report.m
+    )?;
+
+    if report.total_checked == 0 {
+        match report.prime_gen_outcome {
+            PrimeGenOutcome::Empty => writeln!(out, "  No primes in this range: all of it was enumerated and none is prime.")?,
+            PrimeGenOutcome::Sampled { n } => writeln!(out,
+                "  No primes among the {} odd numbers sampled from this range; the rest of the range may still hold some.", n)?,
+            PrimeGenOutcome::Exhaustive if report.timed_out => writeln!(out,
+                "  No primes checked: the per-scale budget ran out before the first chunk; the range was not searched to its end.")?,
+            PrimeGenOutcome::Exhaustive if report.stopped_early => writeln!(out,
+                "  No primes checked: fail-fast stopped the scale at a smaller missed prime before its first chunk.")?,
+            PrimeGenOutcome::Exhaustive => writeln!(out,
+                "  No primes checked: the range holds {} but the max-primes cap allows none.", report.primes_in_range)?,
+        }
+        return Ok(());
+    }
+
+    if let PrimeGenOutcome::Sampled { n } = report.prime_gen_outcome {
+        writeln!(out, "  Coverage: sampled, {} odd numbers spread over the range tested, not exhaustive", n)?;
+    } else {
+        writeln!(out, "  Coverage: exhaustive, every prime in the range enumerated")?;
+    }
+    if report.stopped_early {
+        writeln!(out, "  Stopped early by fail-fast after {} primes, at a missed prime of this or a lower scale",
+            report.total_checked)?;
+    }
+    if report.timed_out {
+        writeln!(out, "  TIMED OUT: stopped after {} primes when the per-scale budget ran out; coverage is not established",
+            report.total_checked)?;
+    }
+    if report.total_checked < report.primes_in_range {
+        writeln!(out, "  Found {} primes; checked a sample of {} spread evenly across the range, not all of them",
+            report.primes_in_range, report.total_checked)?;
+    }
+    if report.coprime_only {
+        writeln!(out, "  Found {} relevant factors of {} coprime to {}.",
+            report.relevant_factors, report.factors_base, report.base)?;
+    } else {
+        writeln!(out, "  Found {} relevant factors of {}.", report.relevant_factors, report.factors_base)?;
+    }
+    if report.factors_dropped > 0 {
+        writeln!(out, "  Factor cap hit: kept the {} of {} relevant factors nearest the middle of the range; coverage may be understated",
+            report.relevant_factors, report.relevant_factors + report.factors_dropped)?;
+    }
+    writeln!(out, "  Generated {} sequence terms.", report.seq_terms)?;
+
+    let found_count = report.factors_only + report.seq_only + report.both_found;
+    match report.methods {
+        Methods::Both => {
+            writeln!(out, "  Primes in range found only near Factors of {} (+/- {}): {}", 
+                report.factors_base, report.max_k, report.factors_only)?;
+            writeln!(out, "  Primes in range found only near Seq({}, {}) (+/- {}): {}", 
+                report.seq_base, report.sequence, report.max_k, report.seq_only)?;
+            writeln!(out, "  Primes in range found near both: {}", report.both_found)?;
+        }
+        Methods::Factors => writeln!(out, "  Primes in range found near Factors of {} (+/- {}), sequence method off: {}",
+            report.factors_base, report.max_k, report.factors_only)?,
+        Methods::Sequence => writeln!(out, "  Primes in range found near Seq({}, {}) (+/- {}), factor method off: {}",
+            report.seq_base, report.sequence, report.max_k, report.seq_only)?,
+    }
+    writeln!(out, "  Total unique primes in range found: {} of {} ({:.2}%)", found_count, report.total_checked,
+        report.coverage_percent.unwrap_or(0.0))?;
+    writeln!(out, "  Primes equal to a factor (distance 0): {}, equal to a sequence term (distance 0): {}",
+        report.factors_exact, report.seq_exact)?;
+    if report.direction != Direction::Both {
+        writeln!(out, "  Only primes {} a candidate count as covered by it", match report.direction {
+            Direction::Above => "at or above",
+            _ => "at or below",
+        })?;
+    }
+    writeln!(out, "  Covered primes above their nearest covering candidate: {}, below it: {}, equal to it: {}",
+        report.offsets.above, report.offsets.below, report.offsets.exact)?;
+    if let Some(twins) = &report.twins {
+        writeln!(out, "  Twin prime pairs: {}, fully covered: {}, half covered: {}, uncovered: {}",
+            twins.pairs, twins.both_covered, twins.one_covered, twins.neither_covered)?;
+    }
+    if let Some(gaps) = &report.gaps {
+        writeln!(out, "  Coverage by gap to the previous prime: {}", format_gap_coverage(gaps))?;
+    }
+
+    if let Some(verification) = &report.verification {
+        if verification.disagreements.is_empty() {
+            writeln!(out, "  Primality verified with num_prime: {} primes and {} composites agree",
+                verification.primes_verified, verification.composites_verified)?;
+        } else {
+            writeln!(out, "  PRIMALITY DISAGREEMENTS: {} of {} primes and {} composites checked with num_prime!",
+                verification.disagreements.len(), verification.primes_verified, verification.composites_verified)?;
+            for disagreement in &verification.disagreements {
+                let (found, reference) = if disagreement.listed_as_prime { ("prime", "composite") } else { ("composite", "prime") };
+                writeln!(out, "    {} was taken for {} but num_prime says {}", disagreement.number, found, reference)?;
+            }
+        }
+    }
+
+    let methods = match report.methods {
+        Methods::Both => "the combined scaled methods",
+        Methods::Factors => "the factor method alone",
+        Methods::Sequence => "the sequence method alone",
+    };
+    if report.probable_primes > 0 {
+        writeln!(out, "  Primality: {} primes proven, {} beyond u64 only probable at {} certainty, so their coverage is evidence, not proof",
+            report.proven_primes, report.probable_primes, primality_certainty())?;
+    } else if report.proven_primes > 0 {
+        writeln!(out, "  Primality: all {} checked primes proven", report.proven_primes)?;
+    }
+    if let Some(check) = &report.prime_count_check {
+        let verdict = if check.within_tolerance { "within" } else { "PRIME COUNT OFF, beyond" };
+        writeln!(out, "  Prime count sanity check: found {}, li(end) - li(start) predicts {:.1} ({:+.2} sigma, {} {} sigma)",
+            check.found, check.expected, check.deviation, verdict, SANITY_CHECK_SIGMAS)?;
+    }
+
+    if report.missed.is_empty() {
+        writeln!(out,
+            "  All {} primes checked in range {} are found by {} with k={}.",
+            report.total_checked, report.format_range(), methods, report.max_k
+        )?;
+    } else {
+        writeln!(out,
+            "  Missed {} primes in range {} with k={}!",
+            report.missed.len(), report.format_range(), report.max_k
+        )?;
+        
+        let label = if report.missed.len() <= 10 { "Missed primes" } else { "First 10 missed primes" };
+        writeln!(out, "  {}:", label)?;
+        for missed in report.missed.iter().take(10) {
+            match &missed.nearest {
+                Some((candidate, dist)) => writeln!(out, "    {} (nearest candidate {}, distance {})",
+                    missed.prime, candidate, dist)?,
+                None => writeln!(out, "    {} (no candidates)", missed.prime)?,
+            }
+        }
+    }
+
+    if let Some((dist, prime)) = &report.minimal_k {
+        writeln!(out, "  Minimal k for full coverage of this range: {} (prime {})", dist, prime)?;
+    }
+
+    let buckets: Vec<String> = histogram_labels(&report.distance_bins).iter()
+        .zip(&report.distance_histogram)
+        .map(|(label, count)| format!("{}: {}", label, count))
+        .collect();
+    writeln!(out, "  Nearest-candidate distance histogram: {}", buckets.join(", "))?;
+    let PhaseTimings { prime_gen, factor_gen, seq_gen, proximity } = report.phases;
+    writeln!(out, "  Time by phase: prime generation {}, factor generation {}, sequence generation {}, proximity checks {}",
+        format_duration(prime_gen), format_duration(factor_gen), format_duration(seq_gen), format_duration(proximity))?;
+    writeln!(out, "  Range check completed in: {}", format_duration(report.elapsed))?;
+    Ok(())
+}
+
+// The scale with the most missed primes in a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorstScale {
+    #[serde(serialize_with = "serde_helpers::scale")]
+    pub m: BigUint,
+    pub missed: usize,
+}
+
+// Totals over every scale checked in one run, built by recording each report
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub scales: u64,
+    pub primes_checked: u64,
+    // Primes within k of a factor, and of a sequence term; primes near both count in both
+    pub covered_by_factor: u64,
+    pub covered_by_seq: u64,
+    pub missed: u64,
+    // Checked primes proven prime, and those only probably prime
+    pub proven_primes: u64,
+    pub probable_primes: u64,
+    // Scales whose primes were sampled, cut short by the max-primes cap,
+    // timed out or stopped by fail-fast, so that finding no misses in them proves nothing about the rest
+    pub partial_scales: u64,
+    // Scales that ran past the per-scale timeout, in the order recorded
+    #[serde(serialize_with = "serde_helpers::scales")]
+    pub timed_out_scales: Vec<BigUint>,
+    // Scales that had relevant factors left out by the factor cap
+    pub factor_capped_scales: u64,
+    // The first scale to reach the most misses, or None if nothing was missed
+    pub worst_scale: Option<WorstScale>,
+    // The smallest missed prime, or None if nothing was missed
+    pub first_missed: Option<MissedAt>,
+    // Seed of the sampling decisions, which replays them when set again
+    pub sampling_seed: u64,
+    // Gap analyses of every scale summed, when gaps were analysed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gaps: Option<GapCoverage>,
+    // Whether the run was stopped before reaching its last scale; the counts
+    // cover only the scales checked until then
+    pub interrupted: bool,
+    // The prime farthest from its nearest candidate, or None if no prime was checked
+    pub largest_distance: Option<checkpoint::WorstPrime>,
+    // Numbers the primality cross-check disagreed on, when scales were verified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primality_disagreements: Option<usize>,
+    // Scales whose prime count strayed from li, when the counts were sanity checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prime_count_warnings: Option<usize>,
+    #[serde(flatten, serialize_with = "serde_helpers::elapsed")]
+    pub elapsed: Duration,
+}
+
+impl RunSummary {
+    // Fold one scale into the summary; ties go to the scale recorded first
+    pub fn record(&mut self, report: &RangeReport) {
+        self.scales += 1;
+        self.primes_checked += report.total_checked as u64;
+        self.covered_by_factor += (report.factors_only + report.both_found) as u64;
+        self.covered_by_seq += (report.seq_only + report.both_found) as u64;
+        self.missed += report.missed.len() as u64;
+        self.proven_primes += report.proven_primes as u64;
+        self.probable_primes += report.probable_primes as u64;
+        if report.sampled || report.total_checked < report.primes_in_range || report.timed_out || report.stopped_early {
+            self.partial_scales += 1;
+        }
+        if report.timed_out {
+            self.timed_out_scales.push(report.m.clone());
+        }
+        if let Some(gaps) = &report.gaps {
+            match self.gaps.as_mut() {
+                Some(total) => total.merge(gaps),
+                None => self.gaps = Some(gaps.clone()),
+            }
+        }
+        if report.factors_dropped > 0 {
+            self.factor_capped_scales += 1;
+        }
+
+        if let Some(missed) = report.missed.first() {
+            if self.first_missed.as_ref().is_none_or(|first| missed.prime < first.prime) {
+                self.first_missed = Some(MissedAt { m: report.m.clone(), prime: missed.prime.clone() });
+            }
+        }
+        let missed = report.missed.len();
+        if missed > 0 && self.worst_scale.as_ref().is_none_or(|worst| missed > worst.missed) {
+            self.worst_scale = Some(WorstScale { m: report.m.clone(), missed });
+        }
+        if let Some((k, prime)) = &report.minimal_k {
+            if self.largest_distance.as_ref().is_none_or(|worst| k > &worst.k) {
+                self.largest_distance = Some(checkpoint::WorstPrime { k: k.clone(), m: report.m.clone(), prime: prime.clone() });
+            }
+        }
+        if let Some(verification) = &report.verification {
+            *self.primality_disagreements.get_or_insert(0) += verification.disagreements.len();
+        }
+        if let Some(check) = &report.prime_count_check {
+            *self.prime_count_warnings.get_or_insert(0) += usize::from(!check.within_tolerance);
+        }
+    }
+}
+
+pub fn print_summary(summary: &RunSummary) {
+    println!("\nRun summary over {} scales: {} primes checked", summary.scales, summary.primes_checked);
+    println!("  Covered by factors: {}", summary.covered_by_factor);
+    println!("  Covered by sequence terms: {}", summary.covered_by_seq);
+    println!("  Missed: {}", summary.missed);
+    if summary.probable_primes > 0 {
+        println!("  Primality: {} primes proven, {} only probable at {} certainty; coverage of those is evidence, not proof",
+            summary.proven_primes, summary.probable_primes, primality_certainty());
+    } else if summary.proven_primes > 0 {
+        println!("  Primality: all {} primes proven", summary.proven_primes);
+    }
+    if summary.partial_scales > 0 {
+        println!("  Scales only partly checked (sampled, capped by max-primes, timed out or stopped by fail-fast): {}",
+            summary.partial_scales);
+    }
+    println!("  Sampling seed: {}", summary.sampling_seed);
+    if summary.interrupted {
+        println!("  Interrupted: stopped before every scale was checked; the counts cover only the scales above");
+    }
+    if !summary.timed_out_scales.is_empty() {
+        let scales: Vec<String> = summary.timed_out_scales.iter().map(BigUint::to_string).collect();
+        println!("  Scales timed out: {} (m={})", scales.len(), scales.join(", "));
+    }
+    if summary.factor_capped_scales > 0 {
+        println!("  Scales with factors left out by max-factors: {} (their misses may be the cap's)",
+            summary.factor_capped_scales);
+    }
+    match &summary.worst_scale {
+        Some(worst) => println!("  Worst scale: m={} with {} missed", worst.m, worst.missed),
+        None => println!("  Worst scale: none, no prime was missed"),
+    }
+    if let Some(first) = &summary.first_missed {
+        println!("  First missed prime: {} at m={}", first.prime, first.m);
+    }
+    if let Some(gaps) = &summary.gaps {
+        println!("  Coverage by gap to the previous prime: {}", format_gap_coverage(gaps));
+    }
+    match &summary.largest_distance {
+        Some(worst) => println!("  Largest nearest-candidate distance: {} (prime {} at m={})", worst.k, worst.prime, worst.m),
+        None => println!("  Largest nearest-candidate distance: n/a (no primes checked)"),
+    }
+    match summary.primality_disagreements {
+        Some(0) => println!("  Primality verification: no disagreements"),
+        Some(count) => println!("  PRIMALITY VERIFICATION FAILED: {} numbers disagree, listed in the scale reports above", count),
+        None => {}
+    }
+    match summary.prime_count_warnings {
+        Some(0) => println!("  Prime count sanity check: every enumerated scale is within {} sigma of li", SANITY_CHECK_SIGMAS),
+        Some(count) => println!("  PRIME COUNT SANITY CHECK FAILED: {} scales stray beyond {} sigma of li", count, SANITY_CHECK_SIGMAS),
+        None => {}
+    }
+    println!("Total execution time: {}", format_duration(summary.elapsed));
+}
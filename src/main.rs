@@ -1,420 +1,1421 @@
-use num_bigint::{BigUint, ToBigUint};
-use num_traits::{Zero, One, ToPrimitive};
-use primal::Sieve;
-use rayon::prelude::*;
-use std::env;
+use num_bigint::BigUint;
+use primegen::{
+    approximate_prime_count, base_factorization, candidate_densities, CandidateKind, DENSITY_HEADER, try_factorize_biguint,
+    prepare_range, check_scaled_range, check_prime_in_prepared_range, is_prime_biguint,
+    scale_of, ensure_exhaustive, coverage_percent, histogram_labels, is_strictly_ascending, Sampling,
+    minimal_k_for_range, range_bounds, FirstMiss, PreparedRange, scale_range,
+    format_report, print_summary, csv_row, write_missed, write_attribution, write_candidates, signed_offset,
+    Direction, FactorBase, Methods, ParallelMode, RangeParams, RangeReport, RunSummary, ScaleScan,
+    ATTRIBUTION_HEADER, CANDIDATES_HEADER, CSV_HEADER, JSON_SCHEMA_VERSION, MISSED_HEADER,
+    set_duration_decimals, format_duration, set_primality_certainty, set_shared_sieve_limit, shared_sieve,
+    Certainty, Error, Interval, Result,
+};
+use primegen::checkpoint::Checkpoint;
+use primegen::factor::{
+    divisor_count, divisors_from_factorization, format_factorization, set_trial_division_bound, MAX_TRIAL_DIVISION_BOUND,
+};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indicatif::MultiProgress;
+use log::warn;
+use num_traits::{ToPrimitive, Zero};
+use primegen::par::*;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::str::FromStr;
 use std::time::{Instant, Duration};
-use std::cmp::{min, max};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use num_prime::nt_funcs::is_prime;
-use num_prime::Primality;
+use std::cmp::max;
+use serde::Serialize;
+use structopt::StructOpt;
 
-// Keep the proven value for coverage
-const MAX_K: u64 = 180;
+// Exit statuses, for scripts. A scan with no misses exits with EXIT_COVERED
+// only if it checked every prime of every scale; sampled or capped scales make
+// it EXIT_UNPROVEN, as does a scan stopped by Ctrl-C, and a second Ctrl-C
+// exits at once with EXIT_INTERRUPTED. check-stdin exits with EXIT_MISSED if
+// any prime it read was missed; every other subcommand and mode exits with
+// EXIT_COVERED.
+const EXIT_COVERED: i32 = 0;
+const EXIT_MISSED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+const EXIT_UNPROVEN: i32 = 3;
+const EXIT_INTERRUPTED: i32 = 130;
 
-// Optimization constants
-const PRIME_TEST_CHUNK_SIZE: usize = 10_000;
-const PARALLEL_SCALE_CHUNK_SIZE: u128 = 10;
-const REPORT_INTERVAL: u128 = 1_000;
+// Set by the first Ctrl-C during a coverage scan
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
-// Helper for BigUint subtraction that doesn't panic on underflow
-trait SaturatingSub {
-    fn saturating_sub(&self, other: &Self) -> Self;
+// Turn Ctrl-C into a request to stop the scan: scales already being checked
+// finish, no others are started, and everything checked so far is written
+// out. A second Ctrl-C gives up on that and exits at once.
+#[cfg(unix)]
+fn install_interrupt_handler() {
+    extern "C" fn on_interrupt(_signal: libc::c_int) {
+        // Only async-signal-safe calls here: an atomic swap and _exit
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            unsafe { libc::_exit(EXIT_INTERRUPTED) };
+        }
+    }
+    let handler = on_interrupt as extern "C" fn(libc::c_int);
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
 }
 
-impl SaturatingSub for BigUint {
-    fn saturating_sub(&self, other: &Self) -> Self {
-        if self > other {
-            self - other
-        } else {
-            BigUint::zero()
+// Elsewhere Ctrl-C still ends the process at once
+#[cfg(not(unix))]
+fn install_interrupt_handler() {}
+
+// log2 of n, also for numbers past the range of f64
+fn log2(n: &BigUint) -> f64 {
+    let shift = n.bits().saturating_sub(f64::MANTISSA_DIGITS as u64);
+    (n >> shift).to_f64().map_or(f64::NEG_INFINITY, f64::log2) + shift as f64
+}
+
+// Relative cost of checking every scale in from..=to. A range's cost grows with
+// the size of its numbers, so each scale is weighted by the bit length of m*base;
+// the midpoint's weight stands in for the whole span.
+fn estimated_work(from: &BigUint, to: &BigUint, base: u64) -> f64 {
+    if from > to {
+        return 0.0;
+    }
+    let count = (to - from + 1u32).to_f64().unwrap_or(f64::INFINITY);
+    let midpoint = (from + to) / 2u32;
+    count * log2(&(midpoint * base)).max(1.0)
+}
+
+// Build the structures every range depends on before the timed sweep starts,
+// so the first range doesn't pay for thread pool startup, the shared sieve or
+// factoring the base
+fn warmup(base: u64) -> String {
+    let warmup_start_time = Instant::now();
+
+    // Spin up every worker in the global pool
+    (0..rayon::current_num_threads()).into_par_iter().for_each(|_| {});
+
+    // Build the shared sieve and factor the modulus once
+    let sieve = shared_sieve();
+    let base_factors = base_factorization(base);
+
+    format!("Warmup took {} (sieve up to {}, {} distinct prime factors of {})",
+        format_duration(warmup_start_time.elapsed()), sieve.upper_bound(), base_factors.len(), base)
+}
+
+// The seed of the sampling RNG as given on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SeedChoice {
+    Fixed(u64),
+    // Drawn from the OS's entropy at the start of the run
+    Random,
+}
+
+impl SeedChoice {
+    fn resolve(self) -> u64 {
+        match self {
+            SeedChoice::Fixed(seed) => seed,
+            SeedChoice::Random => rand::random(),
         }
     }
 }
 
-// More efficient factorization for large numbers
-fn get_factors_biguint(n: &BigUint) -> Vec<BigUint> {
-    // For small enough numbers where we can convert to u64, use primal's efficient factorization
-    if let Some(n_u64) = n.to_u64() {
-        let small_factors = match Sieve::new(n_u64 as usize).factor(n_u64 as usize) {
-            Ok(factors) => factors,
-            Err((_, factors)) => factors, // If partially factored, use what we have
-        };
-            
-        let mut all_factors = vec![1u64.to_biguint().unwrap()];
-        
-        // Generate all combinations of prime factors
-        for (prime, max_power) in small_factors {
-            let prime_biguint = (prime as u64).to_biguint().unwrap();
-            let mut new_factors = Vec::new();
-            
-            for factor in &all_factors {
-                let mut current = factor.clone();
-                for _ in 0..max_power {
-                    current = current * &prime_biguint;
-                    new_factors.push(current.clone());
-                }
-            }
-            
-            all_factors.extend(new_factors);
-        }
-        
-        all_factors.sort();
-        return all_factors;
-    }
-    
-    // For larger numbers, use a more efficient approach for our specific needs
-    // For extremely large numbers, we don't need ALL factors, just those near our range
-    // Instead, we'll focus on smaller factors which are more relevant for the pattern
-
-    let mut factors = vec![BigUint::one()];
-    let two = 2u64.to_biguint().unwrap();
-    
-    // Only check potential factors up to sqrt(n)
-    let limit = n.sqrt() + BigUint::one();
-    let mut i = two.clone();
-    
-    while &i <= &limit {
-        if n % &i == BigUint::zero() {
-            factors.push(i.clone());
-            let quotient = n / &i;
-            if &i != &quotient {
-                factors.push(quotient);
+impl FromStr for SeedChoice {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "random" => Ok(SeedChoice::Random),
+            other => other.parse().map(SeedChoice::Fixed)
+                .map_err(|_| Error::Parse { input: other.to_string(), expected: "a seed (u64) or random" }),
+        }
+    }
+}
+
+// How per-range results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Text,
+    // One JSON array holding every range report, written at the end of the run
+    Json,
+    // One JSON object per line for each scale as its batch completes, then a
+    // last line holding the run summary, so a long run can be followed with tail
+    Jsonl,
+    // A header row, then one row per scale as each batch completes
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(Error::Parse { input: other.to_string(), expected: "an output format" }),
+        }
+    }
+}
+
+// Open a CSV file for appending, writing the header if it is new
+fn open_csv_out(path: &Path, header: &str) -> io::Result<CsvWriter> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_empty = file.metadata()?.len() == 0;
+    // Each run appends a gzip member of its own; gzip readers decompress
+    // concatenated members as one stream
+    let mut writer = if path.extension().is_some_and(|ext| ext == "gz") {
+        CsvWriter::Gzip(BufWriter::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        CsvWriter::Plain(BufWriter::new(file))
+    };
+    if is_empty {
+        writeln!(writer, "{}", header)?;
+    }
+    Ok(writer)
+}
+
+// A CSV output file's writer, gzip-compressed when its path ends in .gz
+enum CsvWriter {
+    Plain(BufWriter<File>),
+    Gzip(BufWriter<GzEncoder<File>>),
+}
+
+impl CsvWriter {
+    // Flush everything and, for gzip, write the trailer that ends the member
+    fn finish(self) -> io::Result<()> {
+        match self {
+            CsvWriter::Plain(mut writer) => writer.flush(),
+            CsvWriter::Gzip(writer) => writer.into_inner().map_err(io::IntoInnerError::into_error)?.finish().map(drop),
+        }
+    }
+}
+
+impl Write for CsvWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CsvWriter::Plain(writer) => writer.write(buf),
+            CsvWriter::Gzip(writer) => writer.write(buf),
+        }
+    }
+
+    // A gzip flush ends on a byte boundary, so everything written so far can be
+    // decompressed even if the run dies before finish
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CsvWriter::Plain(writer) => writer.flush(),
+            CsvWriter::Gzip(writer) => writer.flush(),
+        }
+    }
+}
+
+// A CSV output file together with its path, for error messages
+struct CsvOut {
+    path: PathBuf,
+    writer: CsvWriter,
+}
+
+impl CsvOut {
+    fn open(path: Option<PathBuf>, header: &str) -> Result<Option<CsvOut>> {
+        path.map(|path| {
+            let writer = open_csv_out(&path, header).map_err(Error::io(&path))?;
+            Ok(CsvOut { path, writer })
+        }).transpose()
+    }
+
+    // Append every report of a batch and flush, so a crash loses at most one batch
+    fn write(
+        &mut self,
+        reports: &[RangeReport],
+        write: fn(&mut CsvWriter, &RangeReport) -> Result<()>,
+    ) -> Result<()> {
+        self.write_with(|writer| reports.iter().try_for_each(|report| write(writer, report)))
+    }
+
+    // Append whatever rows write produces and flush
+    fn write_with(&mut self, write: impl FnOnce(&mut CsvWriter) -> Result<()>) -> Result<()> {
+        write(&mut self.writer).map_err(|e| match e {
+            Error::Write(source) => Error::Io { path: self.path.clone(), source },
+            other => other,
+        })?;
+        self.writer.flush().map_err(Error::io(&self.path))
+    }
+
+    fn finish(self) -> Result<()> {
+        self.writer.finish().map_err(Error::io(&self.path))
+    }
+}
+
+// Prints the text reports of a batch in scale order while its scales finish in
+// any order: each report is held back until those of every earlier scale of the
+// batch are out, then printed whole
+struct OrderedPrinter {
+    next: usize,
+    pending: BTreeMap<usize, String>,
+}
+
+impl OrderedPrinter {
+    fn new() -> Self {
+        OrderedPrinter { next: 0, pending: BTreeMap::new() }
+    }
+
+    // Take the report of the batch's index-th scale and print every report now in order
+    fn push(&mut self, index: usize, report: String, progress: Option<&MultiProgress>) {
+        self.pending.insert(index, report);
+        while let Some(report) = self.pending.remove(&self.next) {
+            // Keep the bars of the other scales from drawing over the report
+            match progress {
+                Some(progress) => progress.suspend(|| print!("{}", report)),
+                None => print!("{}", report),
             }
+            self.next += 1;
         }
-        i = i + BigUint::one();
     }
-    
-    // Add the number itself as a factor
-    factors.push(n.clone());
-    factors.sort();
-    factors
 }
 
-fn recursive_sequence_generator_optimized(base: &BigUint, max_value: &BigUint) -> Vec<BigUint> {
-    let mut terms = Vec::new();
-    let mut n_i = base.clone();
-    let mut i = BigUint::one();
-    
-    while &n_i <= max_value {
-        terms.push(n_i.clone());
-        i += BigUint::one();
-        n_i += &i;
+// Scales are numbered from 1, since m = 0 would name the empty range (0, 0]
+fn parse_scale(s: &str) -> Result<BigUint> {
+    match s.parse::<BigUint>() {
+        Ok(m) if !m.is_zero() => Ok(m),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a scale m >= 1" }),
     }
-    
-    terms
 }
 
-fn check_proximity_biguint(prime: &BigUint, candidates: &[BigUint], max_k: &BigUint) -> bool {
-    for candidate in candidates {
-        let diff = if prime > candidate {
-            prime - candidate
-        } else {
-            candidate - prime
-        };
-        
-        if &diff <= max_k {
-            return true;
-        }
-    }
-    false
-}
-
-fn is_prime_biguint(n: &BigUint) -> bool {
-    // Use num_prime for larger numbers
-    // Convert small numbers to u64 for faster checking
-    if let Some(n_u64) = n.to_u64() {
-        if n_u64 <= 1 {
-            return false;
-        }
-        
-        // Use primal's is_prime for small numbers (faster)
-        if n_u64 <= u32::MAX as u64 {
-            let sieve = Sieve::new(min(n_u64 as usize + 1, 10_000_000));
-            return sieve.is_prime(n_u64 as usize);
-        }
-    }
-    
-    // Use num_prime for larger numbers with default config (None)
-    match is_prime(n, None) {
-        Primality::Yes => true,
-        _ => false
-    }
-}
-
-fn generate_primes_in_range(range_start: &BigUint, range_end: &BigUint) -> Vec<BigUint> {
-    let mut primes = Vec::new();
-    
-    // If the range is small enough to convert to u64, use primal's efficient sieve
-    if let (Some(start_u64), Some(end_u64)) = (range_start.to_u64(), range_end.to_u64()) {
-        let sieve = Sieve::new(end_u64 as usize + 1);
-        return sieve.primes_from(0)
-            .take_while(|&p| p <= end_u64 as usize)
-            .filter(|&p| p > start_u64 as usize)
-            .map(|p| p.to_biguint().unwrap())
-            .collect();
-    }
-    
-    // For larger ranges, use parallel chunks with primality testing
-    let range_size = range_end - range_start;
-    
-    // If range is too large, sample primes instead of checking every number
-    if let Some(size) = range_size.to_u64() {
-        if size > 1_000_000 {
-            println!("  Range is very large ({} to {}). Using sampling approach.", range_start, range_end);
-            
-            // Start at an odd number
-            let mut start = range_start.clone();
-            if &start % 2u32 == BigUint::zero() {
-                start += BigUint::one();
+// Scale ranges from a --ranges-file: one `min_m max_m` pair per line, with
+// blank lines and lines starting with # skipped
+fn read_ranges(path: &Path) -> Result<Vec<(BigUint, BigUint)>> {
+    let contents = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let invalid = || Error::Parse { input: line.to_string(), expected: "a line 'min_m max_m' with 1 <= min_m <= max_m" };
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [min_m, max_m] => match (parse_scale(min_m), parse_scale(max_m)) {
+                    (Ok(min_m), Ok(max_m)) if min_m <= max_m => Ok((min_m, max_m)),
+                    _ => Err(invalid()),
+                },
+                _ => Err(invalid()),
             }
-            
-            // Check only odd numbers in parallel
-            let step = 2u32.to_biguint().unwrap();
-            let max_candidates = 1_000_000;  // Limit for massive ranges
-            
-            let candidates: Vec<BigUint> = (0..max_candidates)
-                .map(|i| {
-                    start.clone() + &step * i.to_biguint().unwrap()
-                })
-                .take_while(|n| n <= range_end)
-                .collect();
-                
-            return candidates.into_par_iter()
-                .filter(|n| is_prime_biguint(n))
-                .collect();
-        }
-    }
-    
-    // For smaller but still large ranges, check each odd number
-    let mut start = range_start.clone();
-    if &start % 2u32 == BigUint::zero() {
-        start += BigUint::one();
-    }
-    
-    let step = 2u32.to_biguint().unwrap();
-    let two = 2u32.to_biguint().unwrap();
-    
-    let mut candidates = Vec::new();
-    let mut current = start;
-    
-    while &current <= range_end {
-        candidates.push(current.clone());
-        current += &step;
-    }
-    
-    // Special case for 2 if it's in the range
-    if range_start < &two && range_end >= &two {
-        primes.push(two);
-    }
-    
-    // Test primality in parallel
-    let additional_primes: Vec<BigUint> = candidates.into_par_iter()
-        .filter(|n| is_prime_biguint(n))
-        .collect();
-    
-    primes.extend(additional_primes);
-    primes.sort();
-    primes
+        })
+        .collect()
 }
 
-fn check_scaled_range(m: u128, max_k: u64, max_primes_to_check: usize) {
-    let start_time = Instant::now();
-    let m_biguint = m.to_biguint().unwrap();
-    let max_k_biguint = max_k.to_biguint().unwrap();
+// The reports of one span of scales, labeled with its bounds in JSON output
+#[derive(Serialize)]
+struct RangeSection {
+    #[serde(serialize_with = "primegen::serde_helpers::scale")]
+    min_m: BigUint,
+    #[serde(serialize_with = "primegen::serde_helpers::scale")]
+    max_m: BigUint,
+    reports: Vec<RangeReport>,
+}
 
-    // Create range boundaries
-    let range_start_biguint = if m > 1 {
-        (m_biguint.clone() - BigUint::one()) * 360u64
-    } else {
-        BigUint::one() // Start from 1 for m=1 range
-    };
-    let range_end_biguint = m_biguint.clone() * 360u64;
-
-    println!(
-        "\n--- Checking Primes in Range ({}, {}] (Scale m={}) ---",
-        range_start_biguint, range_end_biguint, m
-    );
-
-    // --- Get Primes in the Range ---
-    println!("  Generating primes in range...");
-    let primes_in_range = generate_primes_in_range(&range_start_biguint, &range_end_biguint);
-    
-    // Limit the number of primes for very large ranges
-    let primes_to_check = if primes_in_range.len() > max_primes_to_check {
-        println!("  Found {} primes, limiting check to {} samples for efficiency", 
-            primes_in_range.len(), max_primes_to_check);
-        primes_in_range.into_iter().take(max_primes_to_check).collect()
-    } else {
-        primes_in_range
-    };
-    
-    let total_primes_to_check = primes_to_check.len();
-    
-    if total_primes_to_check == 0 {
-        println!("  No primes in this range.");
-        return;
-    }
-
-    println!("  Will check {} primes in this range.", total_primes_to_check);
-    
-    // --- Generate Candidates ---
-    
-    // --- Scaled Method 1 Candidates (Factors of m * 360) ---
-    println!("  Generating factors of {}...", m * 360);
-    let factors_base = m_biguint.clone() * 360u64;
-    let all_factors_of_base = get_factors_biguint(&factors_base);
-    
-    // Filter factors to only include those near the range
-    let relevant_factors: Vec<BigUint> = all_factors_of_base.into_par_iter()
-        .filter(|f| {
-            // Include factors that might be within max_k of a prime in the range
-            f >= &(range_start_biguint.clone().saturating_sub(&max_k_biguint)) &&
-            f <= &(range_end_biguint.clone() + &max_k_biguint)
+// JSON output of a scan: the reports, on their own or labeled by range, and the
+// run summary. JSON Lines output ends with the summary on its own.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonOutput<'a> {
+    Reports { reports: Vec<RangeReport>, summary: &'a RunSummary },
+    Ranges { ranges: Vec<RangeSection>, summary: &'a RunSummary },
+    Summary { summary: &'a RunSummary },
+}
+
+// A top-level JSON value with JSON_SCHEMA_VERSION added to its fields
+#[derive(Serialize)]
+struct Versioned<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    value: &'a T,
+}
+
+impl<'a, T: Serialize> Versioned<'a, T> {
+    fn new(value: &'a T) -> Self {
+        Versioned { schema_version: JSON_SCHEMA_VERSION, value }
+    }
+}
+
+// One line of JSON Lines output. Stdout is line buffered, so each line is
+// flushed as it is printed and an interrupted run keeps every finished line.
+fn print_json_line<T: Serialize>(value: &T) {
+    println!("{}", serde_json::to_string(&Versioned::new(value)).expect("JSON output is always serializable"));
+}
+
+// Totals of a span of scales, after its last report
+fn print_totals(totals: &Checkpoint, max_k: u64) {
+    println!("\nSweep totals: {} primes checked, {} only near factors, {} only near sequence terms, {} near both, {} missed",
+        totals.primes_checked, totals.factors_only, totals.seq_only, totals.both_found, totals.missed);
+    let found = totals.primes_checked - totals.missed;
+    if let Some(percent) = coverage_percent(found as usize, totals.primes_checked as usize) {
+        println!("Sweep coverage: {:.4}% of checked primes within k={} of a candidate", percent, max_k);
+    }
+    match &totals.worst {
+        Some(worst) => println!(
+            "Minimal k covering the entire sweep: {} (prime {} at m={})", worst.k, worst.prime, worst.m
+        ),
+        None => println!("Minimal k covering the entire sweep: n/a (no primes checked)"),
+    }
+}
+
+// A --search-base range lo..hi of bases, each at least 2
+fn parse_base_range(s: &str) -> Result<(u64, u64)> {
+    let invalid = || Error::Parse { input: s.to_string(), expected: "a base range lo..hi with 2 <= lo <= hi" };
+    let (low, high) = s.split_once("..").ok_or_else(invalid)?;
+    match (low.trim().parse::<u64>(), high.trim().parse::<u64>()) {
+        (Ok(low), Ok(high)) if 2 <= low && low <= high => Ok((low, high)),
+        _ => Err(invalid()),
+    }
+}
+
+// A sample size of at least one odd number
+fn parse_sample_size(s: &str) -> Result<u64> {
+    match s.parse::<u64>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a sample size >= 1" }),
+    }
+}
+
+// A chunk size or interval of at least one
+fn parse_count(s: &str) -> Result<usize> {
+    match s.parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a count >= 1" }),
+    }
+}
+
+// A per-scale budget in seconds, fractions allowed
+fn parse_timeout(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(secs),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a timeout in seconds > 0" }),
+    }
+}
+
+fn parse_trial_division_bound(s: &str) -> Result<u64> {
+    match s.parse::<u64>() {
+        Ok(bound) if (2..=MAX_TRIAL_DIVISION_BOUND).contains(&bound) => Ok(bound),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a trial division bound from 2 to 10,000,000" }),
+    }
+}
+
+fn parse_window_factor(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(factor) if factor >= 0.0 && factor.is_finite() => Ok(factor),
+        _ => Err(Error::Parse { input: s.to_string(), expected: "a window factor >= 0" }),
+    }
+}
+
+// Fully-resolved scan parameters; doubles as the `scan` subcommand's arguments
+#[derive(Debug, Clone, Serialize, StructOpt)]
+struct Config {
+    /// The minimum scale factor to test (at least 1)
+    #[structopt(long, default_value = "1", parse(try_from_str = parse_scale))]
+    #[serde(serialize_with = "primegen::serde_helpers::scale")]
+    min_m: BigUint,
+
+    /// The maximum scale factor to test (at least 1)
+    #[structopt(long, default_value = "10", parse(try_from_str = parse_scale))]
+    #[serde(serialize_with = "primegen::serde_helpers::scale")]
+    max_m: BigUint,
+
+    /// Check the scale ranges listed in this file, one `min_m max_m` pair per line, instead of
+    /// --min-m to --max-m. Ranges are reported separately and labeled in JSON output
+    #[structopt(long, parse(from_os_str), conflicts_with_all = &["min-m", "max-m", "checkpoint"])]
+    ranges_file: Option<PathBuf>,
+
+    /// The maximum number of primes to check per range; a range with more has that many checked,
+    /// evenly spaced over all of its primes
+    #[structopt(long = "max-primes", default_value = "100000")]
+    max_primes_to_check: usize,
+
+    /// Check every prime of every range: never sample, lift the --max-primes cap, and
+    /// fail up front if a range is too wide beyond u64 to enumerate
+    #[structopt(long, conflicts_with_all = &["sample", "max-primes-to-check"])]
+    exhaustive: bool,
+
+    /// Test this many evenly spread odd numbers of every range holding more, instead of
+    /// sampling only ranges beyond u64 wider than 1,000,000
+    #[structopt(long, parse(try_from_str = parse_sample_size))]
+    sample: Option<u64>,
+
+    /// The modulus to test instead of 360. Ranges become ((m-1)*base, m*base],
+    /// factors are taken of m*base and the sequence starts at (m-1)*base + base/2 + 1
+    #[structopt(long, default_value = "360")]
+    base: u64,
+
+    /// Start each scale's sequence this far past (m-1)*base instead of at base/2 + 1,
+    /// just past the middle of the range (181 for base 360)
+    #[structopt(long)]
+    seq_offset: Option<u64>,
+
+    /// The proximity threshold a prime must be within of some candidate
+    #[structopt(long, default_value = "180")]
+    max_k: u64,
+
+    /// Check the scales with every base in lo..hi (inclusive) instead of --base, and print
+    /// the bases ranked by the fewest missed primes, then the smallest k covering them all
+    #[structopt(long, parse(try_from_str = parse_base_range), conflicts_with = "base")]
+    search_base: Option<(u64, u64)>,
+
+    /// Number of worker threads to scan with; defaults to rayon's choice (one per CPU)
+    #[structopt(long)]
+    threads: Option<usize>,
+
+    /// What to parallelize: `scales` checks a batch of scales at once with each scale's
+    /// primes checked sequentially, `primes` checks one scale at a time with its primes
+    /// checked in parallel. Scales suits sweeps over many narrow ranges, primes suits few wide ones
+    #[structopt(long, default_value = "scales", possible_values = &["scales", "primes"])]
+    parallel_mode: ParallelMode,
+
+    /// Numbers tested for primality, and primes checked for coverage, per parallel batch.
+    /// Larger chunks cut scheduling overhead; smaller ones hold fewer numbers at once
+    #[structopt(long, default_value = "10000", parse(try_from_str = parse_count))]
+    chunk_size: usize,
+
+    /// Primes checked between progress bar updates and trace-level progress messages
+    #[structopt(long, default_value = "1000", parse(try_from_str = parse_count))]
+    report_interval: usize,
+
+    /// Bound of the sieve shared by all primality tests; larger values go to num_prime
+    #[structopt(long, default_value = "10000000")]
+    sieve_limit: u64,
+
+    /// Largest trial divisor when factoring; numbers with a larger square root have the primes below
+    /// it divided out and the rest split with Pollard's rho instead of a sieve up to their square root.
+    /// At most 10,000,000, past which trial division costs far more than rho saves
+    #[structopt(long, default_value = "10000", parse(try_from_str = parse_trial_division_bound))]
+    max_trial_division: u64,
+
+    /// How thoroughly numbers beyond u64 are tested: `bpsw` (Baillie-PSW, repeatable),
+    /// `strict` (Baillie-PSW plus a random base) or `fast` (num_prime's default, with random
+    /// bases). Numbers passing the tests are probable primes and count as prime
+    #[structopt(long, default_value = "bpsw", possible_values = &["fast", "bpsw", "strict"])]
+    certainty: Certainty,
+
+    /// Seed of the RNG placing the sample blocks of sampled scales and picking the primes of scales
+    /// capped by --max-primes, so a run can be replayed exactly; `random` draws one, which the run
+    /// prints. num_prime's random primality bases are not covered by it
+    #[structopt(long, default_value = "0")]
+    seed: SeedChoice,
+
+    /// Decimal places of the seconds in printed durations, which are always given in seconds
+    #[structopt(long, default_value = "3")]
+    duration_decimals: u8,
+
+    /// Output format; json, jsonl and csv write nothing else to stdout. jsonl writes each scale's
+    /// report as one line as soon as it is done, and the run summary last
+    #[structopt(long, default_value = "text", possible_values = &["text", "json", "jsonl", "csv"])]
+    format: OutputFormat,
+
+    /// Append every missed prime to this CSV file as m,prime,nearest_candidate,distance;
+    /// a path ending in .gz is gzip-compressed
+    #[structopt(long, parse(from_os_str))]
+    missed_out: Option<PathBuf>,
+
+    /// Append every checked prime to this CSV file with its distance to the nearest
+    /// factor and sequence term and whether each is within k; a path ending in .gz is gzip-compressed
+    #[structopt(long, parse(from_os_str))]
+    attribution_out: Option<PathBuf>,
+
+    /// Append every relevant factor and sequence term of every scale to this CSV file as
+    /// m,kind,candidate, to plot candidate positions against the primes; a path ending in .gz
+    /// is gzip-compressed
+    #[structopt(long, parse(from_os_str))]
+    dump_candidates: Option<PathBuf>,
+
+    /// Record the highest fully-processed m and the running totals to this file after every batch
+    #[structopt(long, parse(from_os_str))]
+    checkpoint: Option<PathBuf>,
+
+    /// Continue from the scale after the one recorded in --checkpoint, keeping its totals
+    #[structopt(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Skip the scales a previous --format json report with the same base and k found fully
+    /// covered, re-checking only those with misses, sampled or capped ones and new ones
+    #[structopt(long, parse(from_os_str))]
+    skip_covered: Option<PathBuf>,
+
+    /// Build shared structures before the timed sweep so per-range timings reflect steady-state cost
+    #[structopt(long)]
+    warmup: bool,
+
+    /// Time candidate generation at m = min_m, 10*min_m, ... up to max_m instead of checking coverage
+    #[structopt(long)]
+    scaling_probe: bool,
+
+    /// Print each scale's range width, approximate prime count and number of factors of m*base,
+    /// without testing any primes, to size a sweep before running it
+    #[structopt(long)]
+    dry_run: bool,
+
+    /// Instead of checking coverage, count for every candidate (factor or sequence term) how many
+    /// of the scale's primes lie within k of it, turning the proximity check around
+    #[structopt(long)]
+    include_composites: bool,
+
+    /// Print the smallest k covering every prime in the range for each scale instead of testing a fixed k
+    #[structopt(long)]
+    find_min_k: bool,
+
+    /// Only keep factors of m*base that are coprime to the base
+    #[structopt(long)]
+    coprime_candidates: bool,
+
+    /// Keep at most this many relevant factors per scale, those nearest the middle of its
+    /// range; scales that hit the cap say so, since their coverage may be understated
+    #[structopt(long, parse(try_from_str = parse_count))]
+    max_factors: Option<usize>,
+
+    /// Keep the factors within this multiple of k of each scale's range (fractions allowed); past
+    /// 1 the extra factors cannot cover a prime and only change the distances reported
+    #[structopt(long, default_value = "1", parse(try_from_str = parse_window_factor))]
+    candidate_window_factor: f64,
+
+    /// The number whose divisors are the factor candidates at scale m: `linear` for m*base,
+    /// `affine:<c>` for m*base + c or `square` for (m*base)^2; the ranges stay the same
+    #[structopt(long, default_value = "linear")]
+    factor_base: FactorBase,
+
+    /// Whether each scale's range also includes its start: `half-open` for ((m-1)*base, m*base],
+    /// or `closed` for [(m-1)*base, m*base], which counts a prime on the boundary at both scales
+    /// sharing it. The end m*base is included either way
+    #[structopt(long, default_value = "half-open", possible_values = &["half-open", "closed"])]
+    interval: Interval,
+
+    /// Use only this candidate method, or both: with one, each scale reports how many primes
+    /// that method covers on its own and lists those it misses
+    #[structopt(long, default_value = "both", possible_values = &["factors", "sequence", "both"])]
+    only_method: Methods,
+
+    /// Which side of a candidate a prime must lie on to be covered by it: `above` counts only
+    /// candidates at or below the prime, `below` only those at or above it
+    #[structopt(long, default_value = "both", possible_values = &["above", "below", "both"])]
+    direction: Direction,
+
+    /// Cross-check every checked prime, and the odd number after each when it was passed over as
+    /// composite, with num_prime's strict test, and report any disagreement with the number
+    #[structopt(long)]
+    verify: bool,
+
+    /// Compare the number of primes found in every enumerated range with li(end) - li(start)
+    /// and warn when it strays further than chance explains, a cheap guard against a
+    /// primality test that drops primes
+    #[structopt(long)]
+    sanity_check: bool,
+
+    /// Stop checking a scale once it has taken this many seconds, reporting it as timed out and
+    /// only partly checked instead of holding up the rest of the run
+    #[structopt(long, parse(try_from_str = parse_timeout))]
+    per_scale_timeout: Option<f64>,
+
+    /// Stop the scan at the smallest missed prime and report it with its scale, instead of
+    /// counting coverage everywhere; scales above a miss found in parallel stop early too
+    #[structopt(long, conflicts_with = "checkpoint")]
+    fail_fast: bool,
+
+    /// Also report, per scale, how many twin prime pairs (p, p+2) have both, one or
+    /// neither prime within k of a candidate
+    #[structopt(long)]
+    twins: bool,
+
+    /// Also report, per scale and for the whole run, the share of primes covered by gap to the
+    /// previous prime, bucketed by gap size, to see whether primes after large gaps are harder
+    /// to cover
+    #[structopt(long)]
+    gap_analysis: bool,
+
+    /// Inclusive upper bounds of the nearest-candidate distance histogram buckets, ascending;
+    /// one more bucket collects larger distances
+    #[structopt(long, use_delimiter = true, default_value = "10,50,100,180")]
+    distance_bins: Vec<u64>,
+
+    /// Print the nearest-candidate distance histogram of the whole run, to see how much
+    /// slack there is below k
+    #[structopt(long)]
+    distance_histogram: bool,
+
+    /// Don't draw progress bars, e.g. in CI logs. Bars go to stderr and are also
+    /// hidden automatically when it is not a terminal
+    #[structopt(long)]
+    no_progress: bool,
+
+    /// Print the fully-resolved configuration as JSON and exit without running
+    #[structopt(long)]
+    #[serde(skip)]
+    dump_config: bool,
+}
+
+// Parsed once per run, so the size of the scan options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+#[structopt(name = "primegen", about = "A tool to test the 360-prime pattern at massive scales")]
+enum Command {
+    /// Check that every prime in each scale's range is near a candidate
+    Scan(Config),
+
+    /// Print every divisor of a number
+    Factor {
+        n: BigUint,
+
+        /// Only list the divisors, one per line
+        #[structopt(long, conflicts_with = "prime-factorization")]
+        divisors_only: bool,
+
+        /// Only print the prime factorization
+        #[structopt(long)]
+        prime_factorization: bool,
+
+        /// Largest trial divisor, as for scan
+        #[structopt(long, default_value = "10000", parse(try_from_str = parse_trial_division_bound))]
+        max_trial_division: u64,
+
+        /// Give up on n with an error after this many steps of Pollard's rho, which
+        /// are enough to split off prime factors up to about their square
+        #[structopt(long, default_value = "10000000")]
+        max_rho_steps: u64,
+    },
+
+    /// Check whether a single prime is covered at its own scale
+    Check {
+        prime: BigUint,
+
+        /// Scale to check against (at least 1); defaults to the scale whose range contains the prime
+        #[structopt(long, parse(try_from_str = parse_scale))]
+        m: Option<BigUint>,
+
+        #[structopt(long, default_value = "360")]
+        base: u64,
+
+        #[structopt(long, default_value = "180")]
+        max_k: u64,
+
+        /// Sequence offset past (m-1)*base; defaults to base/2 + 1
+        #[structopt(long)]
+        seq_offset: Option<u64>,
+
+        /// Whether the scale's range includes its start, as for scan
+        #[structopt(long, default_value = "half-open", possible_values = &["half-open", "closed"])]
+        interval: Interval,
+    },
+
+    /// Check newline-separated primes read from stdin, printing one CSV row each
+    CheckStdin {
+        /// Scale to check every prime against; defaults to each prime's own scale
+        #[structopt(long, parse(try_from_str = parse_scale))]
+        m: Option<BigUint>,
+
+        #[structopt(long, default_value = "360")]
+        base: u64,
+
+        #[structopt(long, default_value = "180")]
+        max_k: u64,
+
+        /// Sequence offset past (m-1)*base; defaults to base/2 + 1
+        #[structopt(long)]
+        seq_offset: Option<u64>,
+    },
+}
+
+// Counts per bucket with their share of the total and the running share up to
+// each bucket, so the slack below k can be read off directly
+fn print_histogram(bins: &[u64], histogram: &[u64]) {
+    let total: u64 = histogram.iter().sum();
+    let percent = |count: u64| if total == 0 { 0.0 } else { count as f64 * 100.0 / total as f64 };
+    let mut cumulative = 0;
+    println!("Nearest-candidate distance histogram for this run:");
+    for (label, &count) in histogram_labels(bins).iter().zip(histogram) {
+        cumulative += count;
+        println!("  {:>12}: {:>10} ({:6.2}%, {:6.2}% cumulative)", label, count, percent(count), percent(cumulative));
+    }
+}
+
+// Print the minimal k giving full coverage for each scale with the candidates
+// params builds; a scale with a prime no candidate is within base of has none
+fn run_find_min_k(min_m: &BigUint, max_m: &BigUint, params: &RangeParams) {
+    println!("Minimal k for full coverage from m={} to m={} (base={})", min_m, max_m, params.base);
+    println!("{:>20} {:>10}", "m", "min_k");
+
+    let results: Vec<(BigUint, Option<u64>)> = scale_range(min_m, max_m).collect::<Vec<_>>().into_par_iter()
+        .map(|m| {
+            let k = minimal_k_for_range(&m, params);
+            (m, k)
         })
         .collect();
 
-    println!("  Found {} relevant factors.", relevant_factors.len());
-    
-    // --- Scaled Method 2 Candidates (Recursive Sequence terms) ---
-    println!("  Generating sequence terms...");
-    let seq_base = if m > 1 {
-        (m_biguint.clone() - BigUint::one()) * 360u64 + 181u64
-    } else {
-        181u64.to_biguint().unwrap()
-    };
-    
-    // Generate sequence terms within range
-    let seq_terms_in_range = recursive_sequence_generator_optimized(
-        &seq_base, 
-        &(range_end_biguint.clone() + &max_k_biguint)
-    );
-    
-    println!("  Generated {} sequence terms.", seq_terms_in_range.len());
-    
-    // --- Check Coverage in Parallel with Progress Tracking ---
-    println!("  Checking proximity of primes to candidates...");
-    
-    let counter = Arc::new(AtomicUsize::new(0));
-    let progress_interval = max(1, total_primes_to_check / 20); // Report at 5% intervals
-    
-    let factors_found = Arc::new(AtomicUsize::new(0));
-    let seq_found = Arc::new(AtomicUsize::new(0));
-    let not_found = Arc::new(AtomicUsize::new(0));
-    
-    let missed_primes: Vec<BigUint> = primes_to_check.par_iter()
-        .filter_map(|prime| {
-            let idx = counter.fetch_add(1, Ordering::Relaxed);
-            
-            // Show progress
-            if idx % progress_interval == 0 || idx + 1 == total_primes_to_check {
-                let percent = ((idx + 1) as f64 / total_primes_to_check as f64) * 100.0;
-                println!("    Progress: {}/{} primes checked ({:.1}%)", 
-                    idx + 1, total_primes_to_check, percent);
-            }
-            
-            // Check Method 1 (factors)
-            if check_proximity_biguint(prime, &relevant_factors, &max_k_biguint) {
-                factors_found.fetch_add(1, Ordering::Relaxed);
-                return None;
+    for (m, k) in &results {
+        match k {
+            Some(k) => println!("{:>20} {:>10}", m, k),
+            None => println!("{:>20} {:>10}", m, format!("> {}", params.base)),
+        }
+    }
+    // None sorts below every k, so put it above them
+    if let Some((m, k)) = results.iter().max_by_key(|(m, k)| (k.is_none(), *k, std::cmp::Reverse(m))) {
+        match k {
+            Some(k) => println!("Largest minimal k: {} at m={}", k, m),
+            None => println!("Largest minimal k: more than base = {} at m={}", params.base, m),
+        }
+    }
+}
+
+// Check the same scales with every base in bases and rank the bases by misses,
+// then by the largest nearest-candidate distance, which is the smallest k that
+// would have covered every checked prime
+fn run_search_base(bases: (u64, u64), ranges: &[(BigUint, BigUint)], max_primes_to_check: usize, params: &RangeParams) {
+    let (low, high) = bases;
+    println!("Searching bases {} to {} (k={}) over {} scale range(s)", low, high, params.max_k, ranges.len());
+
+    let mut results: Vec<(u64, RunSummary)> = (low..=high).into_par_iter()
+        .map(|base| {
+            let base_params = RangeParams { base, ..params.clone() };
+            let mut summary = RunSummary::default();
+            for m in ranges.iter().flat_map(|(min_m, max_m)| scale_range(min_m, max_m)) {
+                summary.record(&check_scaled_range(&m, &base_params, max_primes_to_check, None));
             }
-            
-            // Check Method 2 (sequence)
-            if check_proximity_biguint(prime, &seq_terms_in_range, &max_k_biguint) {
-                seq_found.fetch_add(1, Ordering::Relaxed);
-                return None;
+            (base, summary)
+        })
+        .collect();
+    let needed_k = |summary: &RunSummary| summary.largest_distance.as_ref().map(|worst| worst.k.clone());
+    results.sort_by(|(a_base, a), (b_base, b)| {
+        (a.missed, needed_k(a), a_base).cmp(&(b.missed, needed_k(b), b_base))
+    });
+
+    println!("{:>6} {:>10} {:>14} {:>10} {:>10} {:>10}", "rank", "base", "primes", "missed", "coverage", "needed_k");
+    for (rank, (base, summary)) in results.iter().enumerate() {
+        let found = (summary.primes_checked - summary.missed) as usize;
+        let coverage = coverage_percent(found, summary.primes_checked as usize)
+            .map_or_else(|| "n/a".to_string(), |percent| format!("{:.4}%", percent));
+        let needed = needed_k(summary).map_or_else(|| "n/a".to_string(), |k| k.to_string());
+        println!("{:>6} {:>10} {:>14} {:>10} {:>10} {:>10}",
+            rank + 1, base, summary.primes_checked, summary.missed, coverage, needed);
+    }
+    if let Some((base, summary)) = results.first() {
+        println!("Best base: {} with {} missed", base, summary.missed);
+    }
+}
+
+// For each scale, how many primes cluster within k of each candidate
+fn run_candidate_density(ranges: &[(BigUint, BigUint)], max_primes_to_check: usize, params: &RangeParams, format: OutputFormat) {
+    let scales = ranges.iter().flat_map(|(min_m, max_m)| scale_range(min_m, max_m));
+    let mut all = Vec::new();
+    if format == OutputFormat::Csv {
+        println!("{}", DENSITY_HEADER);
+    }
+
+    for m in scales {
+        let densities = candidate_densities(&m, params, max_primes_to_check);
+        match format {
+            OutputFormat::Text => {
+                println!("\nPrimes within k={} of each candidate of m={} (base={})", params.max_k, m, params.base);
+                println!("{:>10} {:>24} {:>10}", "kind", "candidate", "primes");
+                for density in &densities {
+                    println!("{:>10} {:>24} {:>10}", density.kind, density.candidate, density.primes_within_k);
+                }
+                for kind in [CandidateKind::Factor, CandidateKind::Sequence] {
+                    let counts: Vec<usize> = densities.iter()
+                        .filter(|density| density.kind == kind)
+                        .map(|density| density.primes_within_k)
+                        .collect();
+                    if !counts.is_empty() {
+                        let label = match kind { CandidateKind::Factor => "factor", CandidateKind::Sequence => "sequence term" };
+                        println!("Mean primes within k of a {}: {:.2}", label,
+                            counts.iter().sum::<usize>() as f64 / counts.len() as f64);
+                    }
+                }
             }
-            
-            // Prime not covered by either method
-            not_found.fetch_add(1, Ordering::Relaxed);
-            Some(prime.clone())
+            OutputFormat::Json => all.extend(densities),
+            OutputFormat::Jsonl => densities.iter().for_each(print_json_line),
+            OutputFormat::Csv => densities.iter().for_each(|density| println!("{},{},{},{}",
+                density.m, density.kind, density.candidate, density.primes_within_k)),
+        }
+    }
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&all).expect("densities are always serializable"));
+    }
+}
+
+// Estimate the work of each scale from its bounds and the factorization of
+// its factor base alone: no primality tests and no candidates
+fn run_dry_run(min_m: &BigUint, max_m: &BigUint, max_primes_to_check: usize, params: &RangeParams) {
+    let base = params.base;
+    println!("Dry run from m={} to m={} (base={})", min_m, max_m, base);
+    println!("{:>20} {:>12} {:>14} {:>10} {:>8}", "m", "width", "approx_primes", "factors", "sampled");
+
+    let estimates: Vec<(BigUint, f64, u64, bool)> = scale_range(min_m, max_m).collect::<Vec<_>>().into_par_iter()
+        .map(|m| {
+            let (range_start, range_end) = range_bounds(&m, base);
+            let approx_primes = approximate_prime_count(&range_start, &range_end);
+            let sampled = params.sampling.applies_to(&params.interval.exclusive_start(&range_start), &range_end);
+            let factors = divisor_count(&params.factor_base.factorization(&m, base));
+            (m, approx_primes, factors, sampled)
         })
         .collect();
 
-    // --- Report Results ---
-    let factors_found = factors_found.load(Ordering::Relaxed);
-    let seq_found = seq_found.load(Ordering::Relaxed);
-    let found_count = factors_found + seq_found;
-    let missed_count = not_found.load(Ordering::Relaxed);
-    
-    println!("  Primes in range found near Factors of {} (+/- {}): {}", 
-        factors_base, max_k, factors_found);
-    println!("  Primes in range found near Seq({}, +i) (+/- {}): {}", 
-        seq_base, max_k, seq_found);
-    println!("  Total unique primes in range found: {}", found_count);
-
-    if missed_count == 0 {
-        println!(
-            "  All {} primes checked in range ({}, {}] are found by the combined scaled methods with k={}.",
-            total_primes_to_check, range_start_biguint, range_end_biguint, max_k
-        );
+    for (m, approx_primes, factors, sampled) in &estimates {
+        println!("{:>20} {:>12} {:>14.1} {:>10} {:>8}", m, base, approx_primes, factors, if *sampled { "yes" } else { "no" });
+    }
+    let total: f64 = estimates.iter().map(|(_, approx_primes, _, _)| approx_primes).sum();
+    let capped: f64 = estimates.iter().map(|(_, approx_primes, _, _)| approx_primes.min(max_primes_to_check as f64)).sum();
+    if max_primes_to_check == usize::MAX {
+        println!("Approximate primes in sweep: {:.0}", total);
     } else {
-        println!(
-            "  Missed {} primes in range ({}, {}] with k={}!",
-            missed_count, range_start_biguint, range_end_biguint, max_k
-        );
-        
-        if missed_primes.len() <= 10 {
-            println!("  Missed primes: {:?}", missed_primes);
-        } else {
-            println!("  First 10 missed primes: {:?}", missed_primes.iter().take(10).collect::<Vec<_>>());
-        }
+        println!("Approximate primes in sweep: {:.0}, of which about {:.0} within --max-primes {}",
+            total, capped, max_primes_to_check);
+    }
+}
+
+// Time candidate generation at m = min_m, 10*min_m, 100*min_m, ... up to max_m
+fn run_scaling_probe(min_m: &BigUint, max_m: &BigUint, params: &RangeParams) {
+    println!("Scaling probe from m={} to m={} (base={}, k={})", min_m, max_m, params.base, params.max_k);
+    println!("{:>20} {:>10} {:>10} {:>10} {:>14} {:>14}",
+        "m", "factors", "relevant", "seq_terms", "factor_secs", "seq_secs");
+
+    let mut m = min_m.clone();
+    while &m <= max_m {
+        let prepared = prepare_range(&m, params);
+        println!("{:>20} {:>10} {:>10} {:>10} {:>14.6} {:>14.6}",
+            m,
+            prepared.total_factors,
+            prepared.relevant_factors.len(),
+            prepared.seq_terms.len(),
+            prepared.factor_gen_time.as_secs_f64(),
+            prepared.seq_gen_time.as_secs_f64());
+
+        m *= 10u32;
     }
-    
-    let duration = start_time.elapsed();
-    println!("  Range check completed in: {:?}", duration);
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    // Enhanced command line options
-    let mut max_m: u128 = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(10); // Default to m=10
-    let mut min_m: u128 = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(1);  // Default to start at m=1
-    let max_primes_to_check: usize = args.get(3).and_then(|s| s.parse().ok()).unwrap_or(100_000); // Cap for very large ranges
-    
-    if min_m > max_m {
-        std::mem::swap(&mut min_m, &mut max_m);
-    }
-
-    println!("Starting prime pattern check from scale m={} to m={}", min_m, max_m);
-    println!("Using MAX_K = {}", MAX_K);
-    println!("Maximum primes to check per range: {}", max_primes_to_check);
-    println!("Parallelism enabled with Rayon ({} threads)", rayon::current_num_threads());
+    // Quiet apart from warnings unless RUST_LOG asks for more
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
-    let overall_start_time = Instant::now();
-    
-    // Process in batches for better progress tracking with large ranges
-    let mut current_m = min_m;
-    
-    while current_m <= max_m {
-        let batch_end = min(current_m + PARALLEL_SCALE_CHUNK_SIZE as u128 - 1, max_m);
-        
-        println!("\nProcessing batch: m={} to m={}", current_m, batch_end);
-        let batch_start_time = Instant::now();
-        
-        (current_m..=batch_end).into_par_iter()
-            .for_each(|m| {
-                check_scaled_range(m, MAX_K, max_primes_to_check);
+    // Argument errors exit like any other error; --help and --version succeed
+    let command = Command::from_args_safe().unwrap_or_else(|e| {
+        if e.use_stderr() {
+            eprintln!("{}", e.message);
+            process::exit(EXIT_ERROR);
+        }
+        println!("{}", e.message);
+        process::exit(EXIT_COVERED);
+    });
+
+    let result = match command {
+        Command::Scan(mut config) => {
+            if config.min_m > config.max_m {
+                std::mem::swap(&mut config.min_m, &mut config.max_m);
+            }
+            match config.threads {
+                Some(threads) => rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(Error::from)
+                    .and_then(|pool| pool.install(|| run_scan(config))),
+                None => run_scan(config),
+            }.map(|summary| summary.as_ref().map_or(EXIT_COVERED, exit_code))
+        }
+        Command::Factor { n, divisors_only, prime_factorization, max_trial_division, max_rho_steps } => {
+            set_trial_division_bound(max_trial_division);
+            run_factor(&n, divisors_only, prime_factorization, max_rho_steps).map(|()| EXIT_COVERED)
+        }
+        Command::Check { prime, m, base, max_k, seq_offset, interval } => {
+            run_check(&prime, m, RangeParams { seq_offset, interval, ..RangeParams::new(base, max_k) }).map(|()| EXIT_COVERED)
+        }
+        Command::CheckStdin { m, base, max_k, seq_offset } => {
+            run_check_stdin(m, RangeParams { seq_offset, ..RangeParams::new(base, max_k) })
+                .map(|missed| if missed > 0 { EXIT_MISSED } else { EXIT_COVERED })
+        }
+    };
+
+    match result {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+// Exit status of a scan: whether it missed a prime, and if not whether every
+// prime of every scale was checked, which makes "no misses" a proof rather
+// than the result of a sample
+fn exit_code(summary: &RunSummary) -> i32 {
+    if summary.missed > 0 {
+        EXIT_MISSED
+    } else if summary.partial_scales > 0 || summary.interrupted {
+        EXIT_UNPROVEN
+    } else {
+        EXIT_COVERED
+    }
+}
+
+// Print the prime factorization of n and then its divisors, or only one of the two
+fn run_factor(n: &BigUint, divisors_only: bool, prime_factorization: bool, max_rho_steps: u64) -> Result<()> {
+    let factorization = try_factorize_biguint(n, max_rho_steps)?;
+    if !divisors_only {
+        println!("{} = {}", n, format_factorization(&factorization));
+    }
+    if prime_factorization {
+        return Ok(());
+    }
+
+    let divisors = divisors_from_factorization(&factorization);
+    if !divisors_only {
+        println!("{} has {} divisors:", n, divisors.len());
+    }
+    for divisor in &divisors {
+        println!("{}", divisor);
+    }
+    Ok(())
+}
+
+// Locate the scale containing prime and report which method covers it
+fn run_check(prime: &BigUint, m: Option<BigUint>, params: RangeParams) -> Result<()> {
+    if !is_prime_biguint(prime) {
+        warn!("{} is not prime", prime);
+    }
+
+    let RangeParams { base, max_k, .. } = params;
+    let m = m.unwrap_or_else(|| scale_of(prime, base));
+
+    let prepared = prepare_range(&m, &params);
+    let coverage = check_prime_in_prepared_range(prime, &m, &prepared, &params);
+    let range = params.interval.format_range(&prepared.range_start, &prepared.range_end);
+    if coverage.in_range {
+        println!("{} lies in range {} (scale m={})", prime, range, m);
+    } else {
+        println!("{} lies outside range {} of scale m={}", prime, range, m);
+    }
+
+    let methods = [
+        (format!("factor of {}", prepared.factors_base), &coverage.nearest_factor, coverage.covered_by_factor()),
+        (format!("term of Seq({}, {})", prepared.seq_base, params.sequence), &coverage.nearest_seq,
+            coverage.covered_by_seq()),
+    ];
+    for (label, nearest, hit) in methods {
+        let verdict = if hit { "covered" } else { "not covered" };
+        match nearest {
+            Some((candidate, dist)) => println!("  Nearest {}: {} (distance {}, offset {:+}), {} with k={}",
+                label, candidate, dist, signed_offset(prime, candidate), verdict, max_k),
+            None => println!("  No {} near the range, not covered", label),
+        }
+    }
+
+    // Name the candidates doing the covering, e.g. "covered by factor 5040 (distance 1)"
+    let covering: Vec<String> = [
+        coverage.covering_factor().map(|(factor, dist)| format!("factor {} (distance {})", factor, dist)),
+        coverage.covering_seq_term().map(|(term, dist)| format!("sequence term {} (distance {})", term, dist)),
+    ].into_iter().flatten().collect();
+    if covering.is_empty() {
+        println!("{} is missed", prime);
+    } else {
+        println!("{} is covered by {}", prime, covering.join(" and "));
+    }
+    Ok(())
+}
+
+// Read one prime per line from stdin and print a CSV row per prime naming the
+// method covering it and the covering candidate, or "missed" with the nearest
+// candidate of either method. Blank lines are skipped, and the candidates of
+// the last scale are kept, so sorted input prepares each scale only once.
+// Returns the number of primes missed.
+fn run_check_stdin(m: Option<BigUint>, params: RangeParams) -> Result<usize> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut prepared: Option<(BigUint, PreparedRange)> = None;
+    let mut missed = 0;
+
+    writeln!(out, "prime,m,method,candidate,distance").map_err(Error::io("<stdout>"))?;
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(Error::io("<stdin>"))?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        let prime: BigUint = input.parse()
+            .map_err(|_| Error::Parse { input: input.to_string(), expected: "a prime" })?;
+
+        let scale = m.clone().unwrap_or_else(|| scale_of(&prime, params.base));
+        if prepared.as_ref().is_none_or(|(cached, _)| cached != &scale) {
+            prepared = Some((scale.clone(), prepare_range(&scale, &params)));
+        }
+        let (_, range) = prepared.as_ref().expect("prepared above");
+        let coverage = check_prime_in_prepared_range(&prime, &scale, range, &params);
+
+        // The nearer covering candidate, or the nearer candidate overall if missed
+        let (method, nearest) = match (coverage.covering_factor(), coverage.covering_seq_term()) {
+            (Some(factor), Some(term)) => ("both", Some(if term.1 < factor.1 { term } else { factor })),
+            (Some(factor), None) => ("factor", Some(factor)),
+            (None, Some(term)) => ("sequence", Some(term)),
+            (None, None) => {
+                missed += 1;
+                let nearest = [&coverage.nearest_factor, &coverage.nearest_seq].into_iter()
+                    .flatten()
+                    .min_by(|a, b| a.1.cmp(&b.1));
+                ("missed", nearest)
+            }
+        };
+        match nearest {
+            Some((candidate, dist)) => writeln!(out, "{},{},{},{},{}", prime, scale, method, candidate, dist),
+            None => writeln!(out, "{},{},{},,", prime, scale, method),
+        }.map_err(Error::io("<stdout>"))?;
+    }
+    out.flush().map_err(Error::io("<stdout>"))?;
+    Ok(missed)
+}
+
+// Returns the summary of the coverage check, or None for the modes that print
+// something else instead
+fn run_scan(config: Config) -> Result<Option<RunSummary>> {
+    if config.dump_config {
+        println!("{}", serde_json::to_string_pretty(&config).expect("Config is always serializable"));
+        return Ok(None);
+    }
+
+    let Config {
+        min_m, max_m, ranges_file, mut max_primes_to_check, exhaustive, sample,
+        base, max_k, seq_offset, search_base, sieve_limit, max_trial_division,
+        certainty, seed, duration_decimals, format,
+        missed_out, attribution_out, dump_candidates, checkpoint, resume, skip_covered,
+        warmup: warmup_enabled, scaling_probe, find_min_k, dry_run, include_composites,
+        coprime_candidates, max_factors, candidate_window_factor, factor_base, interval, only_method, direction,
+        verify, sanity_check, per_scale_timeout, fail_fast, twins, gap_analysis,
+        distance_bins, distance_histogram, no_progress, parallel_mode, chunk_size, report_interval, ..
+    } = config;
+    let bins: Vec<BigUint> = distance_bins.iter().map(|&bound| BigUint::from(bound)).collect();
+    if !is_strictly_ascending(&bins) {
+        let input = distance_bins.iter().map(u64::to_string).collect::<Vec<_>>().join(",");
+        return Err(Error::Parse { input, expected: "ascending distance bins" });
+    }
+    let ranges = match &ranges_file {
+        Some(path) => read_ranges(path)?,
+        None => vec![(min_m.clone(), max_m.clone())],
+    };
+    set_shared_sieve_limit(sieve_limit);
+    set_trial_division_bound(max_trial_division);
+    set_primality_certainty(certainty);
+    let seed = seed.resolve();
+    set_duration_decimals(duration_decimals);
+    let sampling = match (exhaustive, sample) {
+        (true, _) => Sampling::Exhaustive,
+        (false, Some(count)) => Sampling::Sample(count),
+        (false, None) => Sampling::Auto,
+    };
+    if exhaustive {
+        // Every range is base wide, so if any lies too far past u64 the last one does
+        let last_m = ranges.iter().map(|(_, max_m)| max_m).max().unwrap_or(&max_m);
+        ensure_exhaustive(last_m, base)?;
+        max_primes_to_check = usize::MAX;
+    }
+    let params = RangeParams {
+        coprime_only: coprime_candidates,
+        max_factors,
+        candidate_window_factor,
+        factor_base,
+        seq_offset,
+        methods: only_method,
+        direction,
+        parallel_mode,
+        sampling,
+        sampling_seed: seed,
+        interval,
+        twins,
+        gap_analysis,
+        verify,
+        sanity_check,
+        scale_timeout: per_scale_timeout.map(Duration::from_secs_f64),
+        distance_bins,
+        chunk_size,
+        report_interval,
+        ..RangeParams::new(base, max_k)
+    };
+
+    if scaling_probe {
+        ranges.iter().for_each(|(min_m, max_m)| run_scaling_probe(min_m, max_m, &params));
+        return Ok(None);
+    }
+
+    if find_min_k {
+        ranges.iter().for_each(|(min_m, max_m)| run_find_min_k(min_m, max_m, &params));
+        return Ok(None);
+    }
+
+    if let Some(bases) = search_base {
+        run_search_base(bases, &ranges, max_primes_to_check, &params);
+        return Ok(None);
+    }
+
+    // Every range sieved from here on shares one list of base primes, sized for
+    // the largest; a top range past u64 or sampled is never sieved, so none is built
+    let top_m = ranges.iter().map(|(_, max_m)| max_m).max().unwrap_or(&max_m);
+    let (top_start, top_end) = range_bounds(top_m, base);
+    let top_sampled = sampling.applies_to(&params.interval.exclusive_start(&top_start), &top_end);
+    if let Some(end) = top_end.to_u64().filter(|_| !top_sampled) {
+        primegen::sieve::cache_base_primes(end);
+    }
+
+    if include_composites {
+        run_candidate_density(&ranges, max_primes_to_check, &params, format);
+        return Ok(None);
+    }
+
+    if dry_run {
+        ranges.iter().for_each(|(min_m, max_m)| run_dry_run(min_m, max_m, max_primes_to_check, &params));
+        return Ok(None);
+    }
+
+    // Only the coverage scan below stops at a miss, so only it shares the first
+    // one, and only it has partial results worth keeping when interrupted
+    install_interrupt_handler();
+    let first_miss = fail_fast.then(|| Arc::new(FirstMiss::new()));
+    let params = RangeParams { fail_fast: first_miss.clone(), ..params };
+
+    // Machine-readable formats keep stdout free of anything but the results
+    let text = format == OutputFormat::Text;
+    let covered = match &skip_covered {
+        Some(path) => primegen::prior::covered_scales(path, &params)?,
+        None => HashSet::new(),
+    };
+
+    if text {
+        match &ranges_file {
+            Some(path) => println!("Starting prime pattern check over {} scale ranges from {}", ranges.len(), path.display()),
+            None => println!("Starting prime pattern check from scale m={} to m={}", min_m, max_m),
+        }
+        println!("Using base = {}, k = {}, sequence offset = {}{}", base, max_k, params.sequence_offset(),
+            if seq_offset.is_none() { " (base/2 + 1)" } else { "" });
+        if exhaustive {
+            println!("Coverage mode: exhaustive (every prime of every range)");
+        } else {
+            println!("Maximum primes to check per range: {}", max_primes_to_check);
+            println!("Coverage mode: {}", sampling);
+            println!("Sampling seed: {} (replay with --seed {})", seed, seed);
+        }
+        if coprime_candidates {
+            println!("Restricting factor candidates to those coprime to {}", base);
+        }
+        if let Some(secs) = per_scale_timeout {
+            println!("Giving up on any scale still unchecked after {}s", secs);
+        }
+        if candidate_window_factor != 1.0 {
+            println!("Keeping factors within {} of each range ({} x k) instead of k", params.candidate_window(), candidate_window_factor);
+        }
+        if interval == Interval::Closed {
+            println!("Ranges are closed: each scale also checks the primes at its range start");
+        }
+        if factor_base != FactorBase::Linear {
+            println!("Taking factor candidates from the divisors of {} instead of m*base", factor_base);
+        }
+        if let Some(cap) = max_factors {
+            println!("Keeping at most {} relevant factors per scale, those nearest the middle of its range", cap);
+        }
+        match only_method {
+            Methods::Factors => println!("Checking the factor method alone; no sequence terms are generated"),
+            Methods::Sequence => println!("Checking the sequence method alone; no factors are generated"),
+            Methods::Both => {}
+        }
+        if direction != Direction::Both {
+            println!("Counting only candidates a prime lies {} as covering it", match direction {
+                Direction::Above => "at or above",
+                _ => "at or below",
             });
-            
-        current_m = batch_end + 1;
-        
-        let batch_duration = batch_start_time.elapsed();
-        println!("\nBatch completed in: {:?}", batch_duration);
-        
-        // Show projected completion time for remaining batches
-        if current_m <= max_m {
-            let _batches_done = ((current_m - min_m) as f64) / (PARALLEL_SCALE_CHUNK_SIZE as f64);
-            let batches_remaining = ((max_m - current_m + 1) as f64) / (PARALLEL_SCALE_CHUNK_SIZE as f64);
-            let avg_batch_time = batch_duration.as_secs_f64() / PARALLEL_SCALE_CHUNK_SIZE as f64;
-            let est_remaining = Duration::from_secs_f64(avg_batch_time * batches_remaining * PARALLEL_SCALE_CHUNK_SIZE as f64);
-            
-            println!("\nEstimated remaining time: {:?}", est_remaining);
-        }
-    }
-
-    let overall_duration = overall_start_time.elapsed();
-    println!("\nTotal execution time: {:?}", overall_duration);
+        }
+        if let Some(path) = &skip_covered {
+            println!("Skipping the {} scales {} found fully covered", covered.len(), path.display());
+        }
+        if cfg!(feature = "serial") {
+            println!("Parallelism disabled (serial build): scales and primes are checked in order on one thread");
+        } else {
+            println!("Parallelism enabled with Rayon ({} threads, across {})", rayon::current_num_threads(),
+                match parallel_mode { ParallelMode::Scales => "scales", ParallelMode::Primes => "primes within a scale" });
+        }
+    }
+
+    if warmup_enabled {
+        let summary = warmup(base);
+        if text {
+            println!("{}", summary);
+        } else {
+            eprintln!("{}", summary);
+        }
+    }
+
+    if format == OutputFormat::Csv {
+        println!("{}", CSV_HEADER);
+    }
+
+    let mut missed_out = CsvOut::open(missed_out, MISSED_HEADER)?;
+    let mut attribution_out = CsvOut::open(attribution_out, ATTRIBUTION_HEADER)?;
+    let mut candidates_out = CsvOut::open(dump_candidates, CANDIDATES_HEADER)?;
+
+    // One bar per scale of the running batch, drawn to stderr
+    let progress = (!no_progress).then(MultiProgress::new);
+
+    let overall_start_time = Instant::now();
+    // Each range's reports, kept for JSON output
+    let mut sections = Vec::new();
+    let mut summary = RunSummary { sampling_seed: seed, ..RunSummary::default() };
+
+    for (min_m, max_m) in &ranges {
+        if text && ranges_file.is_some() {
+            println!("\n=== Scales m={} to m={} ===", min_m, max_m);
+        }
+
+        let mut totals = Checkpoint {
+            sampling,
+            max_factors,
+            methods: only_method,
+            direction,
+            factor_base,
+            sampling_seed: seed,
+            interval,
+            seq_offset,
+            candidate_window_factor,
+            ..Checkpoint::new(base, max_k, max_primes_to_check, coprime_candidates)
+        };
+        let mut first_m = min_m.clone();
+        if resume {
+            let path = checkpoint.as_deref().expect("--resume requires --checkpoint");
+            let saved = Checkpoint::resume(path, &totals)?;
+            if let Some(last_m) = &saved.last_m {
+                first_m = max(last_m + 1u32, first_m);
+            }
+            if text {
+                if &first_m > max_m {
+                    println!("{} already covers every scale", path.display());
+                } else {
+                    println!("Resuming from m={} using {}", first_m, path.display());
+                }
+            }
+            totals = saved;
+        }
+
+        // Distance histogram summed over the scales of this range
+        let mut histogram = vec![0u64; params.distance_bins.len() + 1];
+        let range_start_time = Instant::now();
+
+        // Process in batches for better progress tracking with large ranges. Once
+        // interrupted no scale is started, and the batch keeps only the scales
+        // below the first one it skipped, so none under the checkpoint's last one
+        // goes unchecked.
+        let mut range_reports = Vec::new();
+        let mut scan = ScaleScan::new(&first_m, max_m, params.clone(), max_primes_to_check)
+            .skipping(covered.clone())
+            .stopping_on(&INTERRUPTED);
+        if let Some(progress) = &progress {
+            scan = scan.with_progress(progress);
+        }
+
+        while let Some((current_m, batch_end)) = scan.upcoming_batch() {
+            if text {
+                println!("\nProcessing batch: m={} to m={}", current_m, batch_end);
+            }
+            let batch_start_time = Instant::now();
+
+            // Scales not started still take their turn, so the reports after them are printed
+            let printer = Mutex::new(OrderedPrinter::new());
+            let batch = scan.next_batch_with(|index, report| {
+                if text {
+                    printer.lock().unwrap().push(index, report.map(format_report).unwrap_or_default(), progress.as_ref());
+                }
+            }).expect("the upcoming batch is checked");
+            summary.interrupted |= batch.stopped;
+            let batch_reports = batch.reports;
+
+            // Reports come back in ascending m, so the first scale to reach the largest distance is kept
+            batch_reports.iter().for_each(|report| totals.record(report));
+            batch_reports.iter().for_each(|report| summary.record(report));
+            for report in &batch_reports {
+                histogram.iter_mut().zip(&report.distance_histogram).for_each(|(sum, &count)| *sum += count as u64);
+            }
+            if let Some(out) = missed_out.as_mut() {
+                out.write(&batch_reports, write_missed)?;
+            }
+            if let Some(out) = attribution_out.as_mut() {
+                out.write(&batch_reports, write_attribution)?;
+            }
+            // Reports only count the candidates, so each scale's are generated again for the dump
+            if let Some(out) = candidates_out.as_mut() {
+                out.write_with(|writer| batch_reports.iter().try_for_each(|report| {
+                    write_candidates(writer, &report.m, &prepare_range(&report.m, &params))
+                }))?;
+            }
+
+            match format {
+                OutputFormat::Text => {}
+                OutputFormat::Json => range_reports.extend(batch_reports),
+                OutputFormat::Jsonl => batch_reports.iter().for_each(print_json_line),
+                OutputFormat::Csv => batch_reports.iter().for_each(|report| println!("{}", csv_row(report))),
+            }
+
+            // Only written once the whole batch is recorded, so a crash resumes at a batch boundary
+            if let Some(path) = checkpoint.as_deref() {
+                totals.save(path)?;
+            }
+
+            let batch_duration = batch_start_time.elapsed();
+            if text {
+                println!("\nBatch completed in: {}", format_duration(batch_duration));
+            }
+
+            // Project the remaining time from the average time per unit of work so far
+            if let (true, Some((next_m, _))) = (text, scan.upcoming_batch()) {
+                let secs_per_work = range_start_time.elapsed().as_secs_f64() / estimated_work(&first_m, &batch_end, base);
+                let remaining_work = estimated_work(&next_m, max_m, base);
+                let est_remaining = Duration::from_secs_f64(secs_per_work * remaining_work);
+
+                println!("\nEstimated remaining time: {}", format_duration(est_remaining));
+            }
+        }
+
+        if text {
+            print_totals(&totals, max_k);
+            if distance_histogram {
+                print_histogram(&params.distance_bins, &histogram);
+            }
+        }
+        sections.push(RangeSection { min_m: min_m.clone(), max_m: max_m.clone(), reports: range_reports });
+
+        if let Some(first) = first_miss.as_ref().and_then(|first| first.get()) {
+            if text {
+                println!("\nFail-fast: {} at m={} is the first missed prime, stopping the scan", first.prime, first.m);
+            }
+            break;
+        }
+        if summary.interrupted {
+            if text {
+                match &totals.last_m {
+                    Some(m) => println!("\nInterrupted: stopping the scan after m={}; every scale up to it was recorded", m),
+                    None => println!("\nInterrupted: stopping the scan before any scale was recorded"),
+                }
+            }
+            break;
+        }
+    }
+
+    for out in [missed_out, attribution_out, candidates_out].into_iter().flatten() {
+        out.finish()?;
+    }
+
+    summary.elapsed = overall_start_time.elapsed();
+    match format {
+        OutputFormat::Text => print_summary(&summary),
+        OutputFormat::Json => {
+            // A single span lists its reports; a ranges file labels each range's reports
+            let output = match ranges_file {
+                Some(_) => JsonOutput::Ranges { ranges: sections, summary: &summary },
+                None => {
+                    let reports = sections.into_iter().flat_map(|section| section.reports).collect();
+                    JsonOutput::Reports { reports, summary: &summary }
+                }
+            };
+            let json = serde_json::to_string_pretty(&Versioned::new(&output)).expect("reports are always serializable");
+            println!("{}", json);
+        }
+        OutputFormat::Jsonl => print_json_line(&JsonOutput::Summary { summary: &summary }),
+        OutputFormat::Csv => {}
+    }
+    Ok(Some(summary))
 }
\ No newline at end of file
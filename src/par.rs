@@ -0,0 +1,37 @@
+// Rayon's parallel iterators, or with the serial feature the same calls on
+// plain sequential iterators: into_par_iter and par_iter then iterate in order
+// on the calling thread, so profiles and bisections are free of work stealing
+// and every result comes out exactly as the parallel path computes it.
+#[cfg(not(feature = "serial"))]
+pub use rayon::prelude::*;
+
+#[cfg(feature = "serial")]
+pub use serial::{IntoParallelIterator, IntoParallelRefIterator};
+
+#[cfg(feature = "serial")]
+mod serial {
+    pub trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+
+    impl<'a, I: 'a + ?Sized> IntoParallelRefIterator<'a> for I
+    where
+        &'a I: IntoIterator,
+    {
+        type Iter = <&'a I as IntoIterator>::IntoIter;
+
+        fn par_iter(&'a self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+}
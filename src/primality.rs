@@ -0,0 +1,66 @@
+// Deterministic Miller-Rabin for numbers that fit in a u64, and the
+// independent reference test --verify checks the fast path against
+use num_bigint::BigUint;
+use num_prime::nt_funcs::is_prime;
+use num_prime::PrimalityTestConfig;
+
+// Strong probable prime bases that together admit no composite below
+// 3.3 * 10^24 (Sorenson and Webster), which covers every u64
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+// Whether n is prime, proven by Miller-Rabin to every base in WITNESSES
+pub fn is_prime_u64_deterministic(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    // Small primes are witnesses themselves, and small factors are cheaper to divide out
+    for &p in &WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    WITNESSES.iter().all(|&a| is_strong_probable_prime(n, a, d, s))
+}
+
+// Whether n passes the strong probable prime test to base a
+fn is_strong_probable_prime(n: u64, a: u64, d: u64, s: u32) -> bool {
+    let mut x = pow_mod(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+fn mul_mod(a: u64, b: u64, n: u64) -> u64 {
+    (a as u128 * b as u128 % n as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, n: u64) -> u64 {
+    let mut result = 1;
+    base %= n;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, n);
+        }
+        base = mul_mod(base, base, n);
+        exp >>= 1;
+    }
+    result
+}
+
+// num_prime's strict test, which shares no code with the sieve or the
+// Miller-Rabin above: it proves u64 values itself and runs Baillie-PSW plus a
+// random base beyond them
+pub fn is_prime_reference(n: &BigUint) -> bool {
+    is_prime(n, Some(PrimalityTestConfig::strict())).probably()
+}
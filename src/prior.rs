@@ -0,0 +1,104 @@
+// Scales a previous `--format json` run has already confirmed, so a re-run
+// can check only the ones it left open
+use crate::error::{Error, Result};
+use crate::{sequence_seed_at, serde_helpers, Direction, FactorBase, Interval, Methods, RangeParams};
+use num_bigint::BigUint;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+// The few fields of a RangeReport needed to tell whether its scale is settled,
+// and with which candidates
+#[derive(Deserialize)]
+struct PriorReport {
+    #[serde(deserialize_with = "serde_helpers::scale_from_json")]
+    m: BigUint,
+    base: u64,
+    max_k: u64,
+    // Absent from reports made before ranges could be closed, which were half-open
+    #[serde(default)]
+    interval: Interval,
+    coprime_only: bool,
+    // Absent from reports made before the factor cap existed, which kept every factor
+    #[serde(default)]
+    max_factors: Option<usize>,
+    // Where the scale's sequence started, (m-1)*base plus the sequence offset
+    #[serde(deserialize_with = "serde_helpers::biguint_from_str")]
+    seq_base: BigUint,
+    // Absent from reports made before methods could be switched off, which used both
+    #[serde(default)]
+    methods: Methods,
+    // Absent from reports made before coverage could be one-sided, which counted both sides
+    #[serde(default)]
+    direction: Direction,
+    // Absent from reports made before the factor base could vary, which took factors of m*base
+    #[serde(default)]
+    factor_base: FactorBase,
+    sampled: bool,
+    primes_in_range: usize,
+    total_checked: usize,
+    // Absent from reports made before scales could time out
+    #[serde(default)]
+    timed_out: bool,
+    missed: Vec<IgnoredAny>,
+}
+
+impl PriorReport {
+    // The first setting in which the report's range or candidates differ from
+    // those a scan with params checks, or None if its coverage carries over
+    fn mismatch(&self, params: &RangeParams) -> Option<&'static str> {
+        [
+            ("base", self.base == params.base),
+            ("k", self.max_k == params.max_k),
+            ("interval", self.interval == params.interval),
+            ("coprime-candidates", self.coprime_only == params.coprime_only),
+            ("factor cap", self.max_factors == params.max_factors),
+            ("sequence offset", self.seq_base == sequence_seed_at(&self.m, params.base, params.sequence_offset())),
+            ("methods", self.methods == params.methods),
+            ("direction", self.direction == params.direction),
+            ("factor base", self.factor_base == params.factor_base),
+        ]
+        .into_iter()
+        .find_map(|(setting, same)| (!same).then_some(setting))
+    }
+}
+
+#[derive(Deserialize)]
+struct PriorSection {
+    reports: Vec<PriorReport>,
+}
+
+// The output of a single span lists its reports, that of a ranges file its sections
+#[derive(Deserialize)]
+struct PriorOutput {
+    #[serde(default)]
+    reports: Vec<PriorReport>,
+    #[serde(default)]
+    ranges: Vec<PriorSection>,
+}
+
+// Scales of the JSON report at path whose every prime was checked and covered.
+// Sampled scales, scales cut short by the max-primes cap or a timeout and
+// scales with misses are left out, so they are checked again. The report must have been produced
+// with the range and candidate settings of params, since coverage by other candidates says
+// nothing about these, and a half-open report never checked a closed range's start.
+pub fn covered_scales(path: &Path, params: &RangeParams) -> Result<HashSet<BigUint>> {
+    let json = fs::read_to_string(path).map_err(Error::io(path))?;
+    let output: PriorOutput = serde_json::from_str(&json)
+        .map_err(|source| Error::InvalidReport { path: path.to_path_buf(), source })?;
+
+    let reports = output.reports.into_iter()
+        .chain(output.ranges.into_iter().flat_map(|section| section.reports));
+    let mut covered = HashSet::new();
+    for report in reports {
+        if let Some(setting) = report.mismatch(params) {
+            return Err(Error::ReportMismatch { path: path.to_path_buf(), setting });
+        }
+        if !report.sampled && !report.timed_out && report.total_checked == report.primes_in_range && report.missed.is_empty() {
+            covered.insert(report.m);
+        }
+    }
+    Ok(covered)
+}
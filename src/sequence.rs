@@ -0,0 +1,130 @@
+use num_bigint::BigUint;
+use std::fmt;
+use std::sync::Arc;
+
+// Step function of a custom sequence: the term after n_i, given n_i and its 1-based index i
+pub type StepFn = dyn Fn(&BigUint, u64) -> BigUint + Send + Sync;
+
+// Rule generating the second method's candidates from a seed term
+#[derive(Clone, Default)]
+pub enum Sequence {
+    // n_{i+1} = n_i + (i + 1), so gaps grow 2, 3, 4, ... as in the original conjecture
+    #[default]
+    Triangular,
+    // n_{i+1} = n_i + step
+    Arithmetic { step: BigUint },
+    // n_{i+1} = f(n_i, i)
+    Custom(Arc<StepFn>),
+}
+
+impl Sequence {
+    // The term following term, which is the i-th (1-based) of the sequence
+    pub fn next_term(&self, term: &BigUint, i: u64) -> BigUint {
+        match self {
+            Sequence::Triangular => term + (i + 1),
+            Sequence::Arithmetic { step } => term + step,
+            Sequence::Custom(step) => step(term, i),
+        }
+    }
+
+    // Every term from first up to max_value, ascending. Generation stops at the
+    // first term that doesn't grow, so the result stays sorted and finite.
+    pub fn terms(&self, first: &BigUint, max_value: &BigUint) -> Vec<BigUint> {
+        self.terms_iter(first, max_value).collect()
+    }
+
+    // The same terms as terms, generated one at a time so a long sequence can
+    // be streamed over without holding it
+    pub fn terms_iter(&self, first: &BigUint, max_value: &BigUint) -> Terms<'_> {
+        Terms { sequence: self, next: Some(first.clone()), i: 1, max_value: max_value.clone() }
+    }
+}
+
+// Iterator over the terms of a sequence up to a bound, as returned by Sequence::terms_iter
+pub struct Terms<'a> {
+    sequence: &'a Sequence,
+    // The next term to yield, or None once a term stopped growing
+    next: Option<BigUint>,
+    // 1-based index of next
+    i: u64,
+    max_value: BigUint,
+}
+
+impl Iterator for Terms<'_> {
+    type Item = BigUint;
+
+    fn next(&mut self) -> Option<BigUint> {
+        let term = self.next.take().filter(|term| term <= &self.max_value)?;
+        let next = self.sequence.next_term(&term, self.i);
+        if next > term {
+            self.next = Some(next);
+            self.i += 1;
+        }
+        Some(term)
+    }
+}
+
+// A sliding view over streamed terms for ascending queries. Each call to
+// covering hands out just the terms that can be nearest to a number in
+// [low, high], so only one window of a long sequence is held at a time.
+pub struct TermWindow<'a> {
+    terms: Terms<'a>,
+    // The largest term passed so far, the nearest from below for what follows
+    below: Option<BigUint>,
+    // The first term not yet passed
+    pending: Option<BigUint>,
+    generated: usize,
+}
+
+impl<'a> TermWindow<'a> {
+    pub fn new(mut terms: Terms<'a>) -> Self {
+        let pending = terms.next();
+        TermWindow { terms, below: None, generated: pending.is_some() as usize, pending }
+    }
+
+    fn advance(&mut self) {
+        self.below = self.pending.take();
+        self.pending = self.terms.next();
+        self.generated += self.pending.is_some() as usize;
+    }
+
+    // The terms from the last one at or below low to the first one at or above
+    // high, ascending. Calls must not go backwards: each low must be at least
+    // the previous high.
+    pub fn covering(&mut self, low: &BigUint, high: &BigUint) -> Vec<BigUint> {
+        while self.pending.as_ref().is_some_and(|term| term <= low) {
+            self.advance();
+        }
+        let mut window: Vec<BigUint> = self.below.iter().cloned().collect();
+        while let Some(term) = &self.pending {
+            window.push(term.clone());
+            if term >= high {
+                break;
+            }
+            self.advance();
+        }
+        window
+    }
+
+    // Generate the rest of the terms and return how many there were in all
+    pub fn finish(mut self) -> usize {
+        self.generated + self.terms.by_ref().count()
+    }
+}
+
+// Short form of the step rule, as in Seq(181, +i)
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sequence::Triangular => write!(f, "+i"),
+            Sequence::Arithmetic { step } => write!(f, "+{}", step),
+            Sequence::Custom(_) => write!(f, "custom"),
+        }
+    }
+}
+
+impl fmt::Debug for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sequence({})", self)
+    }
+}
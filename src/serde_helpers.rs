@@ -0,0 +1,127 @@
+// Serializers for field types serde can't render the way downstream tools want.
+// BigUints are written as decimal strings so no precision is lost in JSON.
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::de;
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+pub fn biguint<S: Serializer>(n: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(n)
+}
+
+// Scales stay JSON numbers while they fit in u64, as every scale did before
+// they were arbitrary-precision, and become decimal strings past that
+pub fn scale<S: Serializer>(m: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+    match m.to_u64() {
+        Some(m) => serializer.serialize_u64(m),
+        None => serializer.collect_str(m),
+    }
+}
+
+pub fn optional_scale<S: Serializer>(m: &Option<BigUint>, serializer: S) -> Result<S::Ok, S::Error> {
+    match m {
+        Some(m) => scale(m, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn scales<S: Serializer>(scales: &[BigUint], serializer: S) -> Result<S::Ok, S::Error> {
+    struct Scale<'a>(&'a BigUint);
+    impl serde::Serialize for Scale<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            scale(self.0, serializer)
+        }
+    }
+    serializer.collect_seq(scales.iter().map(Scale))
+}
+
+// (candidate, distance) pairs as returned by nearest_candidate
+pub fn nearest<S: Serializer>(
+    nearest: &Option<(BigUint, BigUint)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match nearest {
+        Some((candidate, distance)) => {
+            let mut state = serializer.serialize_struct("Nearest", 2)?;
+            state.serialize_field("candidate", &candidate.to_string())?;
+            state.serialize_field("distance", &distance.to_string())?;
+            state.end()
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+// (distance, prime) pairs as stored in RangeReport::minimal_k
+pub fn minimal_k<S: Serializer>(
+    minimal_k: &Option<(BigUint, BigUint)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match minimal_k {
+        Some((distance, prime)) => {
+            let mut state = serializer.serialize_struct("MinimalK", 2)?;
+            state.serialize_field("k", &distance.to_string())?;
+            state.serialize_field("prime", &prime.to_string())?;
+            state.end()
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+// Each duration as <name>_secs in fractional seconds, as before, and
+// <name>_nanos in whole nanoseconds for analysis needing exact values
+pub fn timings<S: Serializer>(timings: &[(&str, Duration)], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(2 * timings.len()))?;
+    for (name, duration) in timings {
+        map.serialize_entry(&format!("{}_secs", name), &duration.as_secs_f64())?;
+        map.serialize_entry(&format!("{}_nanos", name), &duration.as_nanos())?;
+    }
+    map.end()
+}
+
+// An elapsed time as elapsed_secs and elapsed_nanos, for a flattened field
+pub fn elapsed<S: Serializer>(elapsed: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    timings(&[("elapsed", *elapsed)], serializer)
+}
+
+pub fn biguint_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+// A scale written by scale: a number or a decimal string
+struct Scale(BigUint);
+
+impl<'de> Deserialize<'de> for Scale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScaleVisitor;
+
+        impl de::Visitor<'_> for ScaleVisitor {
+            type Value = Scale;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a scale as a non-negative integer or a decimal string")
+            }
+
+            fn visit_u64<E: de::Error>(self, m: u64) -> Result<Scale, E> {
+                Ok(Scale(BigUint::from(m)))
+            }
+
+            fn visit_str<E: de::Error>(self, m: &str) -> Result<Scale, E> {
+                m.parse().map(Scale).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ScaleVisitor)
+    }
+}
+
+pub fn scale_from_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+    Scale::deserialize(deserializer).map(|Scale(m)| m)
+}
+
+pub fn optional_scale_from_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BigUint>, D::Error> {
+    Option::<Scale>::deserialize(deserializer).map(|m| m.map(|Scale(m)| m))
+}
@@ -0,0 +1,131 @@
+use crate::util::{ceil_div, isqrt};
+use primal::Sieve;
+use std::cmp::max;
+use std::sync::{Arc, Mutex};
+
+// Numbers sieved per segment; small enough for the flags to stay in cache
+const SEGMENT_LEN: u64 = 1 << 18;
+
+// Every prime p with start <= p <= end, in ascending order. Only primes up to
+// sqrt(end) are held in memory; the range itself is sieved one segment at a time.
+pub fn segmented_sieve(start: u64, end: u64) -> Vec<u64> {
+    segments(start, end).flatten().collect()
+}
+
+// The base primes shared by every range sieved after cache_base_primes
+static BASE_PRIMES: Mutex<Option<Arc<BasePrimes>>> = Mutex::new(None);
+
+// Every prime up to limit, enough to cross off the composites of any range
+// ending at or below limit^2
+#[derive(Debug)]
+pub struct BasePrimes {
+    limit: u64,
+    primes: Vec<u64>,
+}
+
+impl BasePrimes {
+    pub fn new(limit: u64) -> Self {
+        let primes = Sieve::new(limit as usize + 1)
+            .primes_from(2)
+            .map(|p| p as u64)
+            .take_while(|&p| p <= limit)
+            .collect();
+        BasePrimes { limit, primes }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn primes(&self) -> &[u64] {
+        &self.primes
+    }
+
+    // Whether these primes suffice to sieve a range ending at end
+    pub fn covers(&self, end: u64) -> bool {
+        isqrt(&end) <= self.limit
+    }
+}
+
+// Sieve the base primes up to sqrt(end) once, so every later range ending at or
+// below end reuses them instead of building its own. A cache that already
+// covers end is kept; a smaller one is replaced.
+pub fn cache_base_primes(end: u64) -> Arc<BasePrimes> {
+    let mut cached = BASE_PRIMES.lock().unwrap();
+    match cached.as_ref() {
+        Some(base) if base.covers(end) => Arc::clone(base),
+        _ => Arc::clone(cached.insert(Arc::new(BasePrimes::new(isqrt(&end))))),
+    }
+}
+
+// The cached base primes if they cover end, or else a list built for this range alone
+fn base_primes_for(end: u64) -> Arc<BasePrimes> {
+    match BASE_PRIMES.lock().unwrap().as_ref() {
+        Some(base) if base.covers(end) => Arc::clone(base),
+        _ => Arc::new(BasePrimes::new(isqrt(&end))),
+    }
+}
+
+// The primes of start..=end one segment at a time, for callers that consume
+// them as they go rather than holding the whole range
+pub fn segments(start: u64, end: u64) -> Segments {
+    let base_primes = if max(start, 2) > end { Arc::new(BasePrimes::new(0)) } else { base_primes_for(end) };
+    segments_with(start, end, base_primes)
+}
+
+// segments with the given base primes, which must cover end
+pub fn segments_with(start: u64, end: u64, base_primes: Arc<BasePrimes>) -> Segments {
+    let start = max(start, 2);
+    debug_assert!(start > end || base_primes.covers(end));
+
+    Segments {
+        base_primes,
+        is_composite: Vec::new(),
+        low: (start <= end).then_some(start),
+        end,
+    }
+}
+
+// Iterator over the primes of each segment of a range, as returned by segments
+pub struct Segments {
+    base_primes: Arc<BasePrimes>,
+    is_composite: Vec<bool>,
+    // Start of the next segment, or None once the range is done
+    low: Option<u64>,
+    end: u64,
+}
+
+impl Iterator for Segments {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Vec<u64>> {
+        let low = self.low?;
+        let high = low.saturating_add(SEGMENT_LEN - 1).min(self.end);
+        let len = (high - low + 1) as usize;
+        self.is_composite.clear();
+        self.is_composite.resize(len, false);
+
+        for &p in self.base_primes.primes() {
+            if p * p > high {
+                break;
+            }
+
+            // Smaller multiples of p were already crossed off by smaller primes
+            let first = match ceil_div(&low, &p).checked_mul(p) {
+                Some(multiple) => max(multiple, p * p),
+                None => continue,
+            };
+            let mut multiple = first;
+            while multiple <= high {
+                self.is_composite[(multiple - low) as usize] = true;
+                multiple = match multiple.checked_add(p) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+
+        self.low = if high == self.end { None } else { Some(high + 1) };
+        Some((0..len).filter(|&i| !self.is_composite[i]).map(|i| low + i as u64).collect())
+    }
+}
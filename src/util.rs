@@ -0,0 +1,38 @@
+// Small numeric helpers shared by the proximity checks, factorization and sieve
+use num_bigint::BigUint;
+use num_integer::{Integer, Roots};
+use num_traits::Zero;
+
+// Helper for BigUint subtraction that doesn't panic on underflow
+pub trait SaturatingSub {
+    fn saturating_sub(&self, other: &Self) -> Self;
+}
+
+impl SaturatingSub for BigUint {
+    fn saturating_sub(&self, other: &Self) -> Self {
+        if self > other {
+            self - other
+        } else {
+            BigUint::zero()
+        }
+    }
+}
+
+// The distance |a - b|, whichever of the two is larger
+pub fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+// a / b rounded up, for u64 and BigUint alike; b must not be zero
+pub fn ceil_div<T: Integer>(a: &T, b: &T) -> T {
+    a.div_ceil(b)
+}
+
+// Largest r with r * r <= n, for u64 and BigUint alike
+pub fn isqrt<T: Roots>(n: &T) -> T {
+    n.sqrt()
+}
@@ -0,0 +1,135 @@
+use num_bigint::BigUint;
+use primegen::checkpoint::Checkpoint;
+use primegen::prior::covered_scales;
+use primegen::{check_scaled_range, Direction, Error, FactorBase, Interval, Methods, RangeParams, DEFAULT_BASE, MAX_K};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+
+fn record_scales(checkpoint: &mut Checkpoint, scales: std::ops::RangeInclusive<u32>) {
+    for m in scales {
+        checkpoint.record(&check_scaled_range(&BigUint::from(m), &RangeParams::new(DEFAULT_BASE, MAX_K), 100_000, None));
+    }
+}
+
+#[test]
+fn resumed_totals_match_an_uninterrupted_sweep() {
+    let mut uninterrupted = Checkpoint::new(DEFAULT_BASE, MAX_K, 100_000, false);
+    record_scales(&mut uninterrupted, 1..=12);
+
+    let path = env::temp_dir().join(format!("primegen-checkpoint-{}.json", std::process::id()));
+    let mut first_half = Checkpoint::new(DEFAULT_BASE, MAX_K, 100_000, false);
+    record_scales(&mut first_half, 1..=5);
+    first_half.save(&path).unwrap();
+
+    let mut resumed = Checkpoint::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(resumed, first_half);
+    assert_eq!(resumed.last_m, Some(BigUint::from(5u32)));
+    record_scales(&mut resumed, 6..=12);
+
+    assert_eq!(resumed, uninterrupted);
+    assert_eq!(resumed.worst.as_ref().map(|w| w.m.clone()), Some(BigUint::from(6u32)));
+}
+
+#[test]
+fn scales_past_u64_round_trip_as_strings() {
+    let m = BigUint::from(10u32).pow(30);
+    let mut checkpoint = Checkpoint::new(DEFAULT_BASE, MAX_K, 100_000, false);
+    checkpoint.record(&check_scaled_range(&m, &RangeParams::new(DEFAULT_BASE, MAX_K), 100_000, None));
+
+    let path = env::temp_dir().join(format!("primegen-checkpoint-huge-{}.json", std::process::id()));
+    checkpoint.save(&path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["last_m"], m.to_string());
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn resume_rejects_mismatched_or_corrupt_checkpoints() {
+    let path = env::temp_dir().join(format!("primegen-checkpoint-bad-{}.json", std::process::id()));
+    Checkpoint::new(DEFAULT_BASE, MAX_K, 100_000, false).save(&path).unwrap();
+    let other_k = Checkpoint::new(DEFAULT_BASE, 30, 100_000, false);
+    assert!(matches!(Checkpoint::resume(&path, &other_k), Err(Error::CheckpointMismatch { setting: "k", .. })));
+    // Another sequence offset checks other primes against other terms, but the
+    // derived one given explicitly is the same run
+    let fresh = Checkpoint::new(DEFAULT_BASE, MAX_K, 100_000, false);
+    let other_offset = Checkpoint { seq_offset: Some(7), ..fresh.clone() };
+    assert!(matches!(Checkpoint::resume(&path, &other_offset), Err(Error::CheckpointMismatch { setting: "sequence offset", .. })));
+    let derived_offset = Checkpoint { seq_offset: Some(fresh.sequence_offset()), ..fresh.clone() };
+    assert!(Checkpoint::resume(&path, &derived_offset).is_ok());
+    // A wider window finds nearer candidates for some primes, so a smaller worst k
+    let wider = Checkpoint { candidate_window_factor: 2.0, ..fresh };
+    assert!(matches!(Checkpoint::resume(&path, &wider), Err(Error::CheckpointMismatch { setting: "candidate window factor", .. })));
+
+    fs::write(&path, "not json").unwrap();
+    assert!(matches!(Checkpoint::load(&path), Err(Error::InvalidCheckpoint { .. })));
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(Checkpoint::load(&path), Err(Error::Io { .. })));
+}
+
+#[test]
+fn skip_covered_keeps_only_fully_covered_scales() {
+    let params = RangeParams::new(DEFAULT_BASE, 100);
+    let reports: Vec<_> = (1..=4u32).map(|m| check_scaled_range(&BigUint::from(m), &params, 100_000, None)).collect();
+    let capped = check_scaled_range(&BigUint::from(1u32), &params, 3, None);
+    let path = env::temp_dir().join(format!("primegen-prior-{}.json", std::process::id()));
+    fs::write(&path, serde_json::json!({ "reports": reports, "summary": {} }).to_string()).unwrap();
+
+    let covered = covered_scales(&path, &params).unwrap();
+    let expected: HashSet<BigUint> = reports.iter().filter(|r| r.missed.is_empty()).map(|r| r.m.clone()).collect();
+    assert_eq!(covered, expected);
+    assert!(covered.contains(&BigUint::from(1u32)) && !covered.contains(&BigUint::from(3u32)));
+    assert!(matches!(covered_scales(&path, &RangeParams::new(DEFAULT_BASE, MAX_K)), Err(Error::ReportMismatch { setting: "k", .. })));
+    // A half-open report never checked the start a closed scan would
+    assert!(matches!(covered_scales(&path, &RangeParams { interval: Interval::Closed, ..params.clone() }), Err(Error::ReportMismatch { setting: "interval", .. })));
+    // Nor did one with other candidates check these
+    for (other, expected) in [
+        (RangeParams { coprime_only: true, ..params.clone() }, "coprime-candidates"),
+        (RangeParams { max_factors: Some(4), ..params.clone() }, "factor cap"),
+        (RangeParams { seq_offset: Some(7), ..params.clone() }, "sequence offset"),
+        (RangeParams { direction: Direction::Above, ..params.clone() }, "direction"),
+        (RangeParams { factor_base: FactorBase::Affine { c: 1 }, ..params.clone() }, "factor base"),
+    ] {
+        match covered_scales(&path, &other) {
+            Err(Error::ReportMismatch { setting, .. }) => assert_eq!(setting, expected),
+            result => panic!("expected a {} mismatch, got {:?}", expected, result),
+        }
+    }
+    // A given offset equal to the derived one checks the same sequence
+    let derived = RangeParams { seq_offset: Some(params.sequence_offset()), ..params.clone() };
+    assert_eq!(covered_scales(&path, &derived).unwrap(), covered);
+
+    // A scale cut short by the max-primes cap is checked again even without misses
+    assert!(capped.missed.is_empty() && capped.total_checked < capped.primes_in_range);
+    let sections = serde_json::json!({ "ranges": [{ "min_m": 1, "max_m": 1, "reports": [capped] }] });
+    fs::write(&path, sections.to_string()).unwrap();
+    assert!(covered_scales(&path, &params).unwrap().is_empty());
+
+    // So is one that timed out, although it checked every prime it counted
+    let expired = RangeParams { scale_timeout: Some(std::time::Duration::ZERO), ..params.clone() };
+    let timed_out = check_scaled_range(&BigUint::from(1u32), &expired, usize::MAX, None);
+    assert!(timed_out.missed.is_empty() && timed_out.total_checked == timed_out.primes_in_range);
+    fs::write(&path, serde_json::json!({ "schema_version": 1, "reports": [timed_out], "summary": {} }).to_string()).unwrap();
+    assert!(covered_scales(&path, &params).unwrap().is_empty());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn skip_covered_rejects_a_both_methods_report_for_one_method() {
+    // Scale 1 is covered with both methods but misses primes with factors alone,
+    // so a factors-only run must not take it as settled
+    let both = RangeParams::new(DEFAULT_BASE, 30);
+    let factors_only = RangeParams { methods: Methods::Factors, ..both.clone() };
+    let m = BigUint::from(1u32);
+    let report = check_scaled_range(&m, &both, 100_000, None);
+    assert!(report.missed.is_empty() && !check_scaled_range(&m, &factors_only, 100_000, None).missed.is_empty());
+
+    let path = env::temp_dir().join(format!("primegen-prior-methods-{}.json", std::process::id()));
+    fs::write(&path, serde_json::json!({ "reports": [report], "summary": {} }).to_string()).unwrap();
+    assert!(covered_scales(&path, &both).unwrap().contains(&m));
+    assert!(matches!(covered_scales(&path, &factors_only), Err(Error::ReportMismatch { setting: "methods", .. })));
+    fs::remove_file(&path).unwrap();
+}
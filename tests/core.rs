@@ -0,0 +1,114 @@
+use num_bigint::BigUint;
+use primegen::{
+    approximate_prime_count, check_proximity_biguint, divisor_count_of_scaled_base, get_factors_biguint,
+    get_factors_of_scaled_base, is_prime_biguint, log_integral, recursive_sequence_generator_optimized,
+    primality_certainty, set_primality_certainty, Certainty, SaturatingSub, DEFAULT_SIEVE_LIMIT,
+};
+
+fn big(n: u64) -> BigUint {
+    BigUint::from(n)
+}
+
+fn bigs(values: &[u64]) -> Vec<BigUint> {
+    values.iter().map(|&v| big(v)).collect()
+}
+
+#[test]
+fn primality_edge_cases_and_known_values() {
+    assert!(!is_prime_biguint(&big(0)));
+    assert!(!is_prime_biguint(&big(1)));
+    assert!(is_prime_biguint(&big(2)));
+    assert!(is_prime_biguint(&big(3)));
+    assert!(!is_prime_biguint(&big(4)));
+
+    let small_primes = bigs(&[5, 7, 97, 181, 359, 1801, 5039]);
+    assert!(small_primes.iter().all(is_prime_biguint));
+    let small_composites = bigs(&[9, 91, 360, 561, 1729, 5040]);
+    assert!(!small_composites.iter().any(is_prime_biguint));
+}
+
+#[test]
+fn primality_above_the_sieve_limit() {
+    // Past the shared sieve these go to num_prime, which is deterministic for u64
+    assert!(is_prime_biguint(&big(DEFAULT_SIEVE_LIMIT + 19)));
+    assert!(is_prime_biguint(&big(4_294_967_291)));
+    assert!(is_prime_biguint(&big(18_446_744_073_709_551_557)));
+    assert!(!is_prime_biguint(&big(DEFAULT_SIEVE_LIMIT + 1)));
+    assert!(!is_prime_biguint(&big(4_294_967_297))); // 641 * 6700417
+    assert!(!is_prime_biguint(&big(3_215_031_751))); // strong pseudoprime to bases 2, 3, 5 and 7
+}
+
+#[test]
+fn probable_primes_beyond_u64_count_as_prime() {
+    let two_64_plus_13: BigUint = "18446744073709551629".parse().unwrap();
+    let mersenne_89: BigUint = (BigUint::from(1u32) << 89u32) - 1u32;
+    let two_64_plus_1: BigUint = "18446744073709551617".parse().unwrap(); // 274177 * 67280421310721
+
+    // Every certainty level agrees on these, so tests running alongside can't be affected
+    for certainty in [Certainty::Fast, Certainty::Strict, Certainty::Bpsw] {
+        set_primality_certainty(certainty);
+        assert_eq!(primality_certainty(), certainty);
+        assert!(is_prime_biguint(&two_64_plus_13), "{:?}", certainty);
+        assert!(is_prime_biguint(&mersenne_89), "{:?}", certainty);
+        assert!(!is_prime_biguint(&two_64_plus_1), "{:?}", certainty);
+        assert!(!is_prime_biguint(&(&two_64_plus_13 * &mersenne_89)), "{:?}", certainty);
+    }
+}
+
+#[test]
+fn divisors_of_small_numbers() {
+    assert_eq!(get_factors_biguint(&big(1)), bigs(&[1]));
+    assert_eq!(get_factors_biguint(&big(2)), bigs(&[1, 2]));
+    assert_eq!(get_factors_biguint(&big(12)), bigs(&[1, 2, 3, 4, 6, 12]));
+    assert_eq!(get_factors_biguint(&big(97)), bigs(&[1, 97]));
+    assert_eq!(get_factors_biguint(&big(360)),
+        bigs(&[1, 2, 3, 4, 5, 6, 8, 9, 10, 12, 15, 18, 20, 24, 30, 36, 40, 45, 60, 72, 90, 120, 180, 360]));
+}
+
+#[test]
+fn sequence_terms_grow_by_increasing_steps() {
+    assert_eq!(recursive_sequence_generator_optimized(&big(181), &big(216)), bigs(&[181, 183, 186, 190, 195, 201, 208, 216]));
+    assert_eq!(recursive_sequence_generator_optimized(&big(181), &big(181)), bigs(&[181]));
+    assert!(recursive_sequence_generator_optimized(&big(181), &big(180)).is_empty());
+}
+
+#[test]
+fn proximity_is_inclusive_at_exactly_k() {
+    let candidates = bigs(&[100, 200]);
+    assert!(check_proximity_biguint(&big(110), &candidates, &big(10)));
+    assert!(check_proximity_biguint(&big(190), &candidates, &big(10)));
+    assert!(!check_proximity_biguint(&big(111), &candidates, &big(10)));
+    assert!(!check_proximity_biguint(&big(189), &candidates, &big(10)));
+    assert!(check_proximity_biguint(&big(100), &candidates, &big(0)));
+    assert!(!check_proximity_biguint(&big(150), &[], &big(1000)));
+}
+
+#[test]
+fn saturating_sub_floors_at_zero() {
+    assert_eq!(big(10).saturating_sub(&big(3)), big(7));
+    assert_eq!(big(3).saturating_sub(&big(3)), big(0));
+    assert_eq!(big(3).saturating_sub(&big(10)), big(0));
+    assert_eq!(big(0).saturating_sub(&big(1)), big(0));
+}
+
+#[test]
+fn prime_count_estimates_track_the_sieve() {
+    // li(10^6) = 78627.549...
+    assert!((log_integral(1e6) - 78_627.549).abs() < 0.01);
+
+    let sieve = primal::Sieve::new(10_000_000);
+    for &(start, end) in &[(0u64, 360), (360_000, 360_360), (1_000_000, 2_000_000), (9_000_000, 10_000_000)] {
+        let actual = sieve.primes_from(0).skip_while(|&p| p as u64 <= start).take_while(|&p| p as u64 <= end).count() as f64;
+        let estimate = approximate_prime_count(&big(start), &big(end));
+        assert!((estimate - actual).abs() <= actual * 0.1 + 5.0, "({}, {}]: estimated {} for {}", start, end, estimate, actual);
+    }
+    assert_eq!(approximate_prime_count(&big(100), &big(100)), 0.0);
+}
+
+#[test]
+fn divisor_counts_match_the_divisor_lists() {
+    for m in 1..=50u64 {
+        let divisors = get_factors_of_scaled_base(&big(m), 360);
+        assert_eq!(divisor_count_of_scaled_base(&big(m), 360), divisors.len() as u64, "m={}", m);
+    }
+}
@@ -0,0 +1,607 @@
+use num_bigint::BigUint;
+use primegen::{
+    check_prime_in_prepared_range, check_primes_against_range, check_scaled_range, check_single_prime, default_seq_offset, format_duration, format_report, generate_primes_in_range, histogram_bucket, histogram_labels,
+    is_strictly_ascending, minimal_k_for_range, prepare_range, previous_prime, range_bounds, scale_of, scan_scales, sequence_seed, Direction, Methods, ParallelMode,
+    write_candidates, FirstMiss, GapCoverage, MissedAt, PrimeGenOutcome, RangeParams, Sampling, RangeReport, RunSummary, ScaleScan, WorstScale, CANDIDATES_HEADER, DEFAULT_BASE,
+};
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn minimal_k_matches_known_small_scales() {
+    let params = RangeParams::new(DEFAULT_BASE, 30);
+    assert_eq!(minimal_k_for_range(&BigUint::from(1u32), &params), Some(29));
+    assert_eq!(minimal_k_for_range(&BigUint::from(6u32), &params), Some(180));
+    assert!((1..=30u32).all(|m| minimal_k_for_range(&BigUint::from(m), &params).is_some_and(|k| k <= DEFAULT_BASE)));
+}
+
+#[test]
+fn minimal_k_follows_the_candidate_settings() {
+    let both = RangeParams::new(DEFAULT_BASE, 30);
+    let factors_only = RangeParams { methods: Methods::Factors, ..both.clone() };
+    for m in (1..=12u32).map(BigUint::from) {
+        let combined = minimal_k_for_range(&m, &both).unwrap();
+        assert!(minimal_k_for_range(&m, &factors_only).unwrap() >= combined, "m={}", m);
+    }
+    // Scale 1 misses primes with factors alone at k = 30, so it needs more
+    let one = BigUint::from(1u32);
+    assert!(minimal_k_for_range(&one, &factors_only).unwrap() > minimal_k_for_range(&one, &both).unwrap());
+    // Every prime of the scale lies below its factor m*base, at most base away
+    assert!(minimal_k_for_range(&one, &RangeParams { direction: Direction::Below, ..factors_only.clone() }).is_some());
+}
+
+#[test]
+fn coverage_buckets_partition_checked_primes() {
+    for m in 1..=30u32 {
+        let report = check_scaled_range(&BigUint::from(m), &RangeParams::new(DEFAULT_BASE, 30), 100_000, None);
+        assert_eq!(
+            report.factors_only + report.seq_only + report.both_found + report.missed.len(),
+            report.total_checked,
+            "m={}", m
+        );
+    }
+}
+
+#[test]
+fn parallel_modes_agree() {
+    // Sequential checks sweep the candidates rather than binary search them,
+    // across chunks of a few primes as well as within one
+    for direction in [Direction::Both, Direction::Above, Direction::Below] {
+        let parallel = RangeParams { direction, parallel_mode: ParallelMode::Primes, ..RangeParams::new(DEFAULT_BASE, 30) };
+        let sequential = RangeParams { parallel_mode: ParallelMode::Scales, ..parallel.clone() };
+        let small_chunks = RangeParams { chunk_size: 7, ..sequential.clone() };
+        for m in [1, 6, 1000u32].map(BigUint::from) {
+            let a = check_scaled_range(&m, &parallel, 100_000, None);
+            for b in [check_scaled_range(&m, &sequential, 100_000, None), check_scaled_range(&m, &small_chunks, 100_000, None)] {
+                assert_eq!((a.factors_only, a.seq_only, a.both_found), (b.factors_only, b.seq_only, b.both_found), "m={}", m);
+                assert_eq!(a.distance_histogram, b.distance_histogram, "m={}", m);
+                assert_eq!(a.minimal_k, b.minimal_k, "m={}", m);
+            }
+        }
+    }
+}
+
+#[test]
+fn single_prime_check_agrees_with_range_check() {
+    let params = RangeParams::new(DEFAULT_BASE, 30);
+    for m in (1..=10u32).map(BigUint::from) {
+        let report = check_scaled_range(&m, &params, 100_000, None);
+        let primes = generate_primes_in_range(&report.range_start, &report.range_end);
+        let coverage: Vec<_> = primes.iter().map(|p| check_single_prime(p, &m, &params)).collect();
+
+        assert!(coverage.iter().all(|c| c.in_range && scale_of(&c.prime, DEFAULT_BASE) == m));
+        assert_eq!(coverage.iter().filter(|c| c.covered_by_factor() && c.covered_by_seq()).count(), report.both_found);
+        assert_eq!(coverage.iter().filter(|c| !c.is_covered()).count(), report.missed.len(), "m={}", m);
+
+        // The attribution names the same nearest candidates as the single-prime check
+        for (c, attribution) in coverage.iter().zip(&report.attribution) {
+            assert_eq!((&c.nearest_factor, &c.nearest_seq), (&attribution.nearest_factor, &attribution.nearest_seq));
+        }
+
+        // Reusing one prepared range gives the same answers as preparing per prime
+        let prepared = prepare_range(&m, &params);
+        for c in &coverage {
+            let reused = check_prime_in_prepared_range(&c.prime, &m, &prepared, &params);
+            assert_eq!((&reused.nearest_factor, &reused.nearest_seq), (&c.nearest_factor, &c.nearest_seq));
+        }
+    }
+
+    let covering = check_single_prime(&BigUint::from(5039u32), &BigUint::from(14u32), &RangeParams::new(DEFAULT_BASE, 180));
+    assert_eq!(covering.covering_factor(), Some(&(BigUint::from(5040u32), BigUint::from(1u32))));
+    assert_eq!(covering.covering_seq_term(), Some(&(BigUint::from(5031u32), BigUint::from(8u32))));
+    let missed = check_single_prime(&BigUint::from(149u32), &BigUint::from(1u32), &RangeParams::new(DEFAULT_BASE, 20));
+    assert_eq!((missed.covering_factor(), missed.covering_seq_term()), (None, None));
+}
+
+#[test]
+fn formatted_reports_are_whole_sections() {
+    let report = check_scaled_range(&BigUint::from(6u32), &RangeParams::new(DEFAULT_BASE, 180), 100_000, None);
+    let text = format_report(&report);
+    assert!(text.starts_with("\n--- Results for Range (1800, 2160] (Scale m=6) ---\n"));
+    assert!(text.contains("  Minimal k for full coverage of this range: 180 (prime 1801)\n"));
+    assert!(text.ends_with(&format!("  Range check completed in: {}\n", format_duration(report.elapsed))));
+}
+
+#[test]
+fn phase_timings_fit_within_the_elapsed_time() {
+    let report = check_scaled_range(&BigUint::from(1000u32), &RangeParams::new(DEFAULT_BASE, 180), 100_000, None);
+    let phases = report.phases;
+    assert!(phases.prime_gen + phases.factor_gen + phases.seq_gen + phases.proximity <= report.elapsed);
+    assert!(phases.proximity > Duration::ZERO);
+    assert!(format_report(&report).contains(&format!(
+        "  Time by phase: prime generation {}, factor generation {}, sequence generation {}, proximity checks {}\n",
+        format_duration(phases.prime_gen), format_duration(phases.factor_gen), format_duration(phases.seq_gen),
+        format_duration(phases.proximity))));
+
+    // JSON carries every duration in both seconds and exact nanoseconds
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["elapsed_nanos"], report.elapsed.as_nanos() as u64);
+    assert_eq!(json["elapsed_secs"], report.elapsed.as_secs_f64());
+    assert_eq!(json["phases"]["proximity_nanos"], phases.proximity.as_nanos() as u64);
+}
+
+#[test]
+fn durations_print_in_seconds_with_fixed_decimals() {
+    assert_eq!(format_duration(Duration::from_millis(250)), "0.250s");
+    assert_eq!(format_duration(Duration::from_nanos(1_500)), "0.000s");
+    assert_eq!(format_duration(Duration::from_secs(90)), "90.000s");
+}
+
+#[test]
+fn small_scales_reproduce_the_committed_coverage_vectors() {
+    let vectors = include_str!("data/coverage_vectors.csv");
+    let rows: Vec<(u32, u64, usize)> = vectors.lines()
+        .filter(|line| !line.starts_with('#'))
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            (fields[0].parse().unwrap(), fields[1].parse().unwrap(), fields[2].parse().unwrap())
+        })
+        .collect();
+    assert!((1..=20).all(|m| rows.iter().any(|&(row_m, k, _)| (row_m, k) == (m, 180))));
+
+    for (m, k, expected_missed) in rows {
+        let report = check_scaled_range(&BigUint::from(m), &RangeParams::new(DEFAULT_BASE, k), usize::MAX, None);
+        assert_eq!(report.missed.len(), expected_missed, "m={}, k={}", m, k);
+    }
+}
+
+#[test]
+fn both_directions_miss_only_what_each_direction_misses() {
+    let check = |direction| {
+        let params = RangeParams { direction, ..RangeParams::new(DEFAULT_BASE, 60) };
+        check_scaled_range(&BigUint::from(6u32), &params, 100_000, None)
+    };
+    let (both, above, below) = (check(Direction::Both), check(Direction::Above), check(Direction::Below));
+    let missed = |report: &RangeReport| report.missed.iter().map(|missed| missed.prime.clone()).collect::<Vec<_>>();
+    let missed_both_ways: Vec<_> = missed(&above).into_iter().filter(|prime| missed(&below).contains(prime)).collect();
+    assert_eq!(missed(&both), missed_both_ways);
+
+    for report in [&both, &above, &below] {
+        let offsets = report.offsets;
+        assert_eq!(offsets.above + offsets.below + offsets.exact, report.total_checked - report.missed.len());
+    }
+    assert_eq!((above.offsets.below, below.offsets.above), (0, 0));
+}
+
+#[test]
+fn single_method_checks_split_the_combined_coverage() {
+    let check = |methods| {
+        let params = RangeParams { methods, ..RangeParams::new(DEFAULT_BASE, 180) };
+        check_scaled_range(&BigUint::from(6u32), &params, 100_000, None)
+    };
+    let (both, factors, sequence) = (check(Methods::Both), check(Methods::Factors), check(Methods::Sequence));
+
+    assert_eq!((factors.seq_terms, factors.seq_only, factors.both_found), (0, 0, 0));
+    assert_eq!(factors.factors_only, both.factors_only + both.both_found);
+    assert_eq!(factors.missed.len(), both.seq_only + both.missed.len());
+    assert_eq!((sequence.relevant_factors, sequence.factors_only, sequence.both_found), (0, 0, 0));
+    assert_eq!(sequence.seq_only, both.seq_only + both.both_found);
+    assert_eq!(sequence.missed.len(), both.factors_only + both.missed.len());
+    assert!(format_report(&factors).contains("  Primes in range found near Factors of 2160 (+/- 180), sequence method off: 26\n"));
+}
+
+#[test]
+fn single_prime_check_reports_nearest_candidates() {
+    let coverage = check_single_prime(&BigUint::from(1801u32), &BigUint::from(6u32), &RangeParams::new(DEFAULT_BASE, 180));
+    assert_eq!(coverage.nearest_seq, Some((BigUint::from(1981u32), BigUint::from(180u32))));
+    assert_eq!(coverage.nearest_factor, Some((BigUint::from(2160u32), BigUint::from(359u32))));
+    assert!(coverage.covered_by_seq() && !coverage.covered_by_factor());
+}
+
+#[test]
+fn huge_scales_report_exact_bounds() {
+    // m*base no longer fits in u128 for the largest scales, so bounds come from BigUint
+    let params = RangeParams::new(DEFAULT_BASE, 180);
+    for m in [u128::MAX / DEFAULT_BASE as u128, u128::MAX].map(BigUint::from) {
+        let report = check_scaled_range(&m, &params, 1_000, None);
+        assert_eq!(report.factors_base, &m * DEFAULT_BASE);
+        assert_eq!(report.range_end, &m * DEFAULT_BASE);
+        assert_eq!(report.range_start, (&m - 1u32) * DEFAULT_BASE);
+    }
+}
+
+#[test]
+fn scales_past_u128_are_checked() {
+    // 10^50 overflows u128 by more than ten orders of magnitude
+    let m = BigUint::from(10u32).pow(50);
+    let (range_start, range_end) = range_bounds(&m, DEFAULT_BASE);
+    let report = check_scaled_range(&m, &RangeParams::new(DEFAULT_BASE, 180), 100_000, None);
+    assert_eq!((&report.range_start, &report.range_end), (&range_start, &range_end));
+    assert_eq!(report.range_end, &m * DEFAULT_BASE);
+    assert!(!report.sampled && report.total_checked == report.primes_in_range && report.total_checked > 0);
+    assert!(report.missed.is_empty(), "m={}", m);
+    assert_eq!(scale_of(&range_end, DEFAULT_BASE), m);
+    assert_eq!(scale_of(&(range_start + 1u32), DEFAULT_BASE), m);
+}
+
+#[test]
+fn sequence_offsets_derive_from_the_base_unless_given() {
+    let big = |n: u32| BigUint::from(n);
+    assert_eq!(default_seq_offset(360), 181);
+    assert_eq!(default_seq_offset(420), 211);
+    assert_eq!(RangeParams::new(360, 180).sequence_offset(), 181);
+    assert_eq!(RangeParams::new(420, 180).sequence_offset(), 211);
+    assert_eq!(prepare_range(&big(2), &RangeParams::new(420, 180)).seq_base, big(631));
+
+    // A given offset holds whatever the base
+    let params = RangeParams { seq_offset: Some(100), ..RangeParams::new(420, 180) };
+    assert_eq!(params.sequence_offset(), 100);
+    assert_eq!(prepare_range(&big(2), &params).seq_base, big(520));
+    let report = check_scaled_range(&big(2), &RangeParams { base: 360, ..params }, 100_000, None);
+    assert_eq!(report.seq_base, big(460));
+}
+
+#[test]
+fn candidate_dumps_list_both_candidate_sets() {
+    let m = BigUint::from(6u32);
+    let prepared = prepare_range(&m, &RangeParams::new(DEFAULT_BASE, 180));
+    let mut out = Vec::new();
+    write_candidates(&mut out, &m, &prepared).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    let expected: Vec<String> = prepared.relevant_factors.iter().map(|f| format!("6,factor,{}", f))
+        .chain(prepared.seq_terms.iter().map(|t| format!("6,sequence,{}", t)))
+        .collect();
+    assert_eq!(text.lines().collect::<Vec<_>>(), expected);
+    assert!(text.starts_with("6,factor,2160\n6,sequence,1981\n6,sequence,1983\n"));
+    assert_eq!(CANDIDATES_HEADER.split(',').count(), 3);
+}
+
+#[test]
+fn range_bounds_and_sequence_seeds() {
+    let big = |n: u128| BigUint::from(n);
+    assert_eq!(range_bounds(&big(0), DEFAULT_BASE), (big(0), big(0)));
+    assert_eq!(range_bounds(&big(1), DEFAULT_BASE), (big(1), big(360)));
+    assert_eq!(range_bounds(&big(2), DEFAULT_BASE), (big(360), big(720)));
+    let m = big(u128::MAX);
+    assert_eq!(range_bounds(&m, DEFAULT_BASE), ((&m - 1u32) * DEFAULT_BASE, &m * DEFAULT_BASE));
+
+    assert_eq!(sequence_seed(&big(0), DEFAULT_BASE), big(0));
+    assert_eq!(sequence_seed(&big(1), DEFAULT_BASE), big(181));
+    assert_eq!(sequence_seed(&big(2), DEFAULT_BASE), big(541));
+    assert_eq!(sequence_seed(&m, DEFAULT_BASE), (&m - 1u32) * DEFAULT_BASE + 181u32);
+    assert_eq!(sequence_seed(&big(1), 30), big(16));
+
+    // Half-open: the start belongs to the scale below and the end to this one
+    for m in [2, 3, 1000].map(big) {
+        let (start, end) = range_bounds(&m, DEFAULT_BASE);
+        assert_eq!(range_bounds(&(&m - 1u32), DEFAULT_BASE).1, start);
+        assert_eq!((scale_of(&start, DEFAULT_BASE), scale_of(&(&start + 1u32), DEFAULT_BASE)), (&m - 1u32, m.clone()));
+        assert_eq!(scale_of(&end, DEFAULT_BASE), m);
+    }
+    // With base 2, scale 2 is (2, 4]: the prime 2 at its start belongs to scale 1
+    let (start, end) = range_bounds(&big(2), 2);
+    assert_eq!(generate_primes_in_range(&start, &end), vec![big(3)]);
+    let (start, end) = range_bounds(&big(1), 3);
+    assert_eq!(generate_primes_in_range(&start, &end), vec![big(2), big(3)]);
+}
+
+#[test]
+fn scale_zero_is_an_empty_range() {
+    let zero = BigUint::from(0u32);
+    let report = check_scaled_range(&zero, &RangeParams::new(DEFAULT_BASE, 180), 100_000, None);
+    assert_eq!(report.range_start, BigUint::from(0u32));
+    assert_eq!(report.range_end, BigUint::from(0u32));
+    assert_eq!((report.total_checked, report.relevant_factors, report.seq_terms), (0, 0, 0));
+    assert!(report.missed.is_empty() && report.minimal_k.is_none() && report.coverage_percent.is_none());
+    assert_eq!(minimal_k_for_range(&zero, &RangeParams::new(DEFAULT_BASE, 180)), Some(0));
+}
+
+#[test]
+fn coverage_percent_is_the_share_of_checked_primes_found() {
+    let one = BigUint::from(1u32);
+    let report = check_scaled_range(&one, &RangeParams::new(DEFAULT_BASE, 5), 100_000, None);
+    let found = report.factors_only + report.seq_only + report.both_found;
+    assert_eq!((found, report.total_checked), (50, 72));
+    assert!((report.coverage_percent.unwrap() - 5000.0 / 72.0).abs() < 1e-9);
+
+    let report = check_scaled_range(&one, &RangeParams::new(DEFAULT_BASE, 180), 100_000, None);
+    assert_eq!(report.coverage_percent, Some(100.0));
+}
+
+#[test]
+fn candidate_sets_are_sorted_and_deduplicated() {
+    for coprime_only in [false, true] {
+        let params = RangeParams { coprime_only, ..RangeParams::new(DEFAULT_BASE, 180) };
+        for m in [1, 2, 6, 720, 1_000_003u32].map(BigUint::from) {
+            let range = prepare_range(&m, &params);
+            assert!(is_strictly_ascending(&range.relevant_factors), "m={}", m);
+            assert!(is_strictly_ascending(&range.seq_terms), "m={}", m);
+        }
+    }
+}
+
+#[test]
+fn factor_cap_keeps_the_factors_nearest_the_middle() {
+    let one = BigUint::from(1u32);
+    let uncapped = prepare_range(&one, &RangeParams::new(DEFAULT_BASE, 180));
+    assert_eq!((uncapped.relevant_factors.len(), uncapped.factors_dropped), (24, 0));
+
+    // Of the 24 divisors of 360, 180, 120 and 90 lie nearest the middle of (1, 360]
+    let params = RangeParams { max_factors: Some(3), ..RangeParams::new(DEFAULT_BASE, 180) };
+    let capped = prepare_range(&one, &params);
+    assert_eq!(capped.relevant_factors, [90u32, 120, 180].map(BigUint::from));
+    assert_eq!(capped.factors_dropped, 21);
+
+    let report = check_scaled_range(&one, &params, 100_000, None);
+    assert_eq!((report.relevant_factors, report.factors_dropped, report.max_factors), (3, 21, Some(3)));
+    let mut summary = RunSummary::default();
+    summary.record(&report);
+    summary.record(&check_scaled_range(&BigUint::from(6u32), &params, 100_000, None));
+    assert_eq!(summary.factor_capped_scales, 1);
+}
+
+#[test]
+fn twin_pairs_are_counted_only_when_asked() {
+    let one = BigUint::from(1u32);
+    assert_eq!(check_scaled_range(&one, &RangeParams::new(DEFAULT_BASE, 180), 100_000, None).twins, None);
+
+    // (3, 5), (5, 7), ... (347, 349): 21 pairs below 360, all within 180 of a candidate
+    let params = RangeParams { twins: true, ..RangeParams::new(DEFAULT_BASE, 180) };
+    let twins = check_scaled_range(&one, &params, 100_000, None).twins.unwrap();
+    assert_eq!((twins.pairs, twins.both_covered), (21, 21));
+
+    let params = RangeParams { twins: true, ..RangeParams::new(DEFAULT_BASE, 5) };
+    for m in 1..=20u32 {
+        let twins = check_scaled_range(&BigUint::from(m), &params, 100_000, None).twins.unwrap();
+        assert_eq!(twins.both_covered + twins.one_covered + twins.neither_covered, twins.pairs, "m={}", m);
+    }
+}
+
+#[test]
+fn gap_analysis_buckets_each_prime_by_the_gap_before_it() {
+    assert_eq!(previous_prime(&BigUint::from(2u32)), None);
+    assert_eq!(previous_prime(&BigUint::from(3u32)), Some(BigUint::from(2u32)));
+    assert_eq!(previous_prime(&BigUint::from(1801u32)), Some(BigUint::from(1789u32)));
+
+    let m = BigUint::from(2u32);
+    assert_eq!(check_scaled_range(&m, &RangeParams::new(DEFAULT_BASE, 180), 100_000, None).gaps, None);
+
+    // Scale 2's primes 367..719 each have a gap, from 359 -> 367 on
+    let params = RangeParams { gap_analysis: true, ..RangeParams::new(DEFAULT_BASE, 60) };
+    let report = check_scaled_range(&m, &params, 100_000, None);
+    let gaps = report.gaps.clone().unwrap();
+    let (range_start, range_end) = range_bounds(&m, DEFAULT_BASE);
+    let primes = generate_primes_in_range(&range_start, &range_end);
+    let mut expected = GapCoverage::new(&gaps.bins);
+    let mut last = BigUint::from(359u32);
+    for prime in &primes {
+        let bucket = histogram_bucket(&gaps.bins, &(prime - &last));
+        expected.primes[bucket] += 1;
+        if report.missed.iter().all(|missed| &missed.prime != prime) {
+            expected.covered[bucket] += 1;
+        }
+        last = prime.clone();
+    }
+    assert_eq!(gaps, expected);
+    assert_eq!(gaps.primes.iter().sum::<usize>(), report.total_checked);
+    assert_eq!(gaps.covered.iter().sum::<usize>(), report.total_checked - report.missed.len());
+
+    // A capped scale checks every other prime or so, and still buckets each by its true gap
+    let capped = check_scaled_range(&m, &params, 20, None).gaps.unwrap();
+    assert_eq!(capped.primes.iter().sum::<usize>(), 20);
+    assert!(capped.primes.iter().zip(&expected.primes).all(|(capped, all)| capped <= all));
+
+    let mut summary = RunSummary::default();
+    summary.record(&report);
+    summary.record(&report);
+    assert_eq!(summary.gaps.unwrap().primes, gaps.primes.iter().map(|count| 2 * count).collect::<Vec<_>>());
+}
+
+#[test]
+fn candidate_window_widens_the_kept_factors_without_changing_coverage() {
+    let narrow = RangeParams::new(DEFAULT_BASE, 60);
+    let wide = RangeParams { candidate_window_factor: 3.0, ..narrow.clone() };
+    assert_eq!(wide.candidate_window(), 180);
+
+    for m in 1..=30u32 {
+        let m = BigUint::from(m);
+        let (range_start, range_end) = range_bounds(&m, DEFAULT_BASE);
+        let kept = prepare_range(&m, &narrow).relevant_factors;
+        let widened = prepare_range(&m, &wide).relevant_factors;
+        assert!(kept.iter().all(|factor| widened.contains(factor)), "m={}", m);
+        let window = BigUint::from(wide.candidate_window());
+        assert!(widened.iter().all(|factor| factor + &window >= range_start && factor <= &(&range_end + &window)), "m={}", m);
+
+        // Factors past k of the range are too far to cover any of its primes
+        let (a, b) = (check_scaled_range(&m, &narrow, 100_000, None), check_scaled_range(&m, &wide, 100_000, None));
+        assert_eq!((a.factors_only, a.both_found, a.missed.len()), (b.factors_only, b.both_found, b.missed.len()), "m={}", m);
+    }
+}
+
+#[test]
+fn reports_tell_prime_free_ranges_from_limited_searches() {
+    let outcome = |m: u32, params: &RangeParams, max_primes| {
+        let report = check_scaled_range(&BigUint::from(m), params, max_primes, None);
+        (report.total_checked, report.prime_gen_outcome)
+    };
+    let params = RangeParams::new(DEFAULT_BASE, 180);
+    assert_eq!(outcome(1, &params, 100_000), (72, PrimeGenOutcome::Exhaustive));
+    assert_eq!(outcome(0, &params, 100_000), (0, PrimeGenOutcome::Empty));
+
+    // (114000010, 114000020] holds no prime
+    let tens = RangeParams::new(10, 5);
+    assert_eq!(outcome(11_400_002, &tens, 100_000), (0, PrimeGenOutcome::Empty));
+
+    // A cap, a sample or a spent budget can check no prime of a range that has some
+    assert_eq!(outcome(1, &params, 0), (0, PrimeGenOutcome::Exhaustive));
+    let sampled = RangeParams { sampling: Sampling::Sample(1), ..params.clone() };
+    assert_eq!(outcome(1000, &sampled, 100_000), (0, PrimeGenOutcome::Sampled { n: 1 }));
+    let no_time = RangeParams { scale_timeout: Some(Duration::ZERO), ..params.clone() };
+    assert_eq!(outcome(1, &no_time, 100_000), (0, PrimeGenOutcome::Exhaustive));
+
+    let report = check_scaled_range(&BigUint::from(11_400_002u32), &tens, 100_000, None);
+    assert!(format_report(&report).contains("none is prime"));
+    let report = check_scaled_range(&BigUint::from(1000u32), &sampled, 100_000, None);
+    assert!(format_report(&report).contains("sampled from this range"));
+}
+
+#[test]
+fn distance_histogram_uses_the_configured_bins() {
+    assert_eq!(histogram_labels(&[10, 50]), ["0-10", "11-50", ">50"]);
+    assert_eq!(histogram_bucket(&[10, 50], &BigUint::from(10u32)), 0);
+    assert_eq!(histogram_bucket(&[10, 50], &BigUint::from(11u32)), 1);
+    assert_eq!(histogram_bucket(&[10, 50], &BigUint::from(51u32)), 2);
+
+    let params = RangeParams { distance_bins: vec![5, 20, 90], ..RangeParams::new(DEFAULT_BASE, 180) };
+    for m in 1..=10u32 {
+        let report = check_scaled_range(&BigUint::from(m), &params, 100_000, None);
+        assert_eq!(report.distance_histogram.len(), 4);
+        assert_eq!(report.distance_histogram.iter().sum::<usize>(), report.total_checked, "m={}", m);
+    }
+}
+
+#[test]
+fn chunk_size_and_report_interval_do_not_change_reports() {
+    let default = RangeParams::new(DEFAULT_BASE, 30);
+    for (chunk_size, report_interval) in [(1, 1), (7, 3), (100_000, 100_000)] {
+        let params = RangeParams { chunk_size, report_interval, ..default.clone() };
+        for m in [1, 6, 1000u32].map(BigUint::from) {
+            let a = check_scaled_range(&m, &default, 100_000, None);
+            let b = check_scaled_range(&m, &params, 100_000, None);
+            assert_eq!((a.total_checked, a.primes_in_range), (b.total_checked, b.primes_in_range), "m={}", m);
+            assert_eq!((a.factors_only, a.seq_only, a.both_found), (b.factors_only, b.seq_only, b.both_found), "m={}", m);
+        }
+    }
+
+    // A cap that splits a chunk still counts every prime in the range
+    let params = RangeParams { chunk_size: 7, ..default };
+    let report = check_scaled_range(&BigUint::from(1u32), &params, 30, None);
+    assert_eq!((report.total_checked, report.primes_in_range), (30, 72));
+}
+
+#[test]
+fn run_summary_aggregates_every_scale() {
+    let params = RangeParams::new(DEFAULT_BASE, 20);
+    let reports: Vec<_> = (1..=6u32).map(|m| check_scaled_range(&BigUint::from(m), &params, 100_000, None)).collect();
+    let mut summary = RunSummary::default();
+    reports.iter().for_each(|report| summary.record(report));
+
+    assert_eq!(summary.scales, 6);
+    assert_eq!(summary.primes_checked, reports.iter().map(|r| r.total_checked as u64).sum::<u64>());
+    assert_eq!(summary.covered_by_factor, reports.iter().map(|r| (r.factors_only + r.both_found) as u64).sum::<u64>());
+    assert_eq!(summary.covered_by_seq, reports.iter().map(|r| (r.seq_only + r.both_found) as u64).sum::<u64>());
+    assert_eq!(summary.missed, reports.iter().map(|r| r.missed.len() as u64).sum::<u64>());
+
+    let most_missed = reports.iter().map(|r| r.missed.len()).max().unwrap();
+    let worst = reports.iter().find(|r| r.missed.len() == most_missed).unwrap();
+    assert_eq!(summary.worst_scale, Some(WorstScale { m: worst.m.clone(), missed: most_missed }));
+    let largest = summary.largest_distance.as_ref().expect("primes were checked");
+    assert_eq!(largest.k, reports.iter().filter_map(|r| r.minimal_k.clone()).map(|(k, _)| k).max().unwrap());
+
+    assert_eq!(summary.partial_scales, 0);
+    summary.record(&check_scaled_range(&BigUint::from(7u32), &params, 10, None));
+    assert_eq!(summary.partial_scales, 1);
+
+    let empty = RunSummary::default();
+    assert_eq!((empty.worst_scale, empty.largest_distance), (None, None));
+}
+
+#[test]
+fn scales_past_their_timeout_stop_and_count_as_partial() {
+    let m = BigUint::from(6u32);
+    let expired = RangeParams { scale_timeout: Some(Duration::ZERO), ..RangeParams::new(DEFAULT_BASE, 180) };
+    let report = check_scaled_range(&m, &expired, 100_000, None);
+    assert!(report.timed_out);
+    assert_eq!(report.total_checked, 0);
+
+    let mut summary = RunSummary::default();
+    summary.record(&report);
+    assert_eq!((summary.partial_scales, summary.timed_out_scales), (1, vec![m.clone()]));
+
+    let generous = RangeParams { scale_timeout: Some(Duration::from_secs(3600)), ..RangeParams::new(DEFAULT_BASE, 180) };
+    let report = check_scaled_range(&m, &generous, 100_000, None);
+    assert!(!report.timed_out);
+    assert_eq!(report.total_checked, report.primes_in_range);
+}
+
+#[test]
+fn given_prime_lists_are_checked_like_generated_ones() {
+    let params = RangeParams::new(DEFAULT_BASE, 30);
+    for m in [1, 6, 1000u32].map(BigUint::from) {
+        let generated = check_scaled_range(&m, &params, usize::MAX, None);
+        let mut primes = generate_primes_in_range(&generated.range_start, &generated.range_end);
+        primes.reverse();
+        primes.push(primes[0].clone());
+
+        let given = check_primes_against_range(&primes, &m, &params);
+        assert_eq!((given.total_checked, given.primes_in_range), (generated.total_checked, generated.primes_in_range), "m={}", m);
+        assert_eq!((given.factors_only, given.seq_only, given.both_found), (generated.factors_only, generated.seq_only, generated.both_found));
+        assert_eq!(given.missed.iter().map(|missed| &missed.prime).collect::<Vec<_>>(),
+            generated.missed.iter().map(|missed| &missed.prime).collect::<Vec<_>>(), "m={}", m);
+        assert_eq!(given.distance_histogram, generated.distance_histogram, "m={}", m);
+    }
+
+    // A partial list is checked on its own, without the rest of the range
+    let report = check_primes_against_range(&[BigUint::from(1801u32)], &BigUint::from(6u32), &RangeParams::new(DEFAULT_BASE, 180));
+    assert_eq!((report.total_checked, report.seq_only, report.missed.len()), (1, 1, 0));
+}
+
+#[test]
+fn fail_fast_stops_at_the_smallest_missed_prime() {
+    let first_miss = Arc::new(FirstMiss::new());
+    let params = RangeParams { fail_fast: Some(first_miss.clone()), chunk_size: 5, ..RangeParams::new(DEFAULT_BASE, 60) };
+
+    // 421 is the first prime more than 60 from every candidate
+    let report = check_scaled_range(&BigUint::from(2u32), &params, 100_000, None);
+    assert!(report.stopped_early);
+    assert_eq!(report.missed[0].prime, BigUint::from(421u32));
+    assert!(report.total_checked < report.primes_in_range);
+    let expected = MissedAt { m: BigUint::from(2u32), prime: BigUint::from(421u32) };
+    assert_eq!(first_miss.get(), Some(expected.clone()));
+
+    // A higher scale gives up at once; a larger miss elsewhere doesn't replace the first
+    let later = check_scaled_range(&BigUint::from(10u32), &params, 100_000, None);
+    assert!(later.stopped_early && later.total_checked == 0);
+    first_miss.record(&BigUint::from(10u32), &BigUint::from(3253u32));
+    assert_eq!(first_miss.get(), Some(expected.clone()));
+
+    let mut summary = RunSummary::default();
+    summary.record(&report);
+    summary.record(&later);
+    assert_eq!((summary.first_missed, summary.partial_scales), (Some(expected), 2));
+
+    // Without fail-fast the same scale is checked in full
+    let full = check_scaled_range(&BigUint::from(2u32), &RangeParams::new(DEFAULT_BASE, 60), 100_000, None);
+    assert!(!full.stopped_early && full.total_checked == full.primes_in_range);
+}
+
+#[test]
+fn scale_scans_yield_every_report_in_order() {
+    let (first, last) = (BigUint::from(1u32), BigUint::from(25u32));
+    let reports: Vec<RangeReport> = scan_scales(&first, &last, DEFAULT_BASE, 180).collect();
+
+    let scales: Vec<BigUint> = reports.iter().map(|report| report.m.clone()).collect();
+    assert_eq!(scales, (1..=25u32).map(BigUint::from).collect::<Vec<_>>());
+    let params = RangeParams::new(DEFAULT_BASE, 180);
+    for report in &reports {
+        let alone = check_scaled_range(&report.m, &params, 100_000, None);
+        assert_eq!((report.total_checked, report.missed.len()), (alone.total_checked, alone.missed.len()));
+    }
+    assert_eq!(reports.iter().map(|report| report.total_checked).sum::<usize>(), 1117);
+}
+
+#[test]
+fn scale_scans_batch_skip_and_stop() {
+    let params = RangeParams::new(DEFAULT_BASE, 180);
+    let skip: HashSet<BigUint> = [3u32, 4].into_iter().map(BigUint::from).collect();
+    let mut scan = ScaleScan::new(&BigUint::from(1u32), &BigUint::from(7u32), params.clone(), 1_000)
+        .with_batch_len(3)
+        .skipping(skip);
+
+    assert_eq!(scan.upcoming_batch(), Some((BigUint::from(1u32), BigUint::from(3u32))));
+    let batch = scan.next_batch().unwrap();
+    assert_eq!(batch.reports.iter().map(|report| report.m.clone()).collect::<Vec<_>>(), [BigUint::from(1u32), BigUint::from(2u32)]);
+    let rest: Vec<BigUint> = scan.map(|report| report.m).collect();
+    assert_eq!(rest, [5u32, 6, 7].map(BigUint::from));
+
+    // A stop flag set before a batch starts skips all of it and ends the scan
+    let stop = AtomicBool::new(true);
+    let mut scan = ScaleScan::new(&BigUint::from(1u32), &BigUint::from(7u32), params, 1_000).stopping_on(&stop);
+    let batch = scan.next_batch().unwrap();
+    assert!(batch.stopped && batch.reports.is_empty());
+    assert!(scan.upcoming_batch().is_none());
+}
@@ -0,0 +1,180 @@
+use num_bigint::{BigUint, ToBigUint};
+use primegen::factor::{
+    divisor_count, divisors_in_range, format_factorization, prime_factorization, set_trial_division_bound, trial_division_bound,
+    try_prime_factorization, DEFAULT_MAX_RHO_STEPS, DEFAULT_TRIAL_DIVISION_BOUND, MAX_TRIAL_DIVISION_BOUND,
+};
+use primegen::{
+    base_factorization, divisors_of_product, factorization_of_scaled_base, factorize_biguint, get_factors_biguint,
+    get_factors_of_scaled_base, prepare_range, try_factorize_biguint, Error, FactorBase, RangeParams,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn factors_thirty_digit_semiprime_quickly() {
+    let p = 1_000_000_007u64.to_biguint().unwrap();
+    let q: BigUint = "100000000000000000039".parse().unwrap();
+    let n = &p * &q;
+    assert_eq!(n.to_string().len(), 30);
+
+    let start = Instant::now();
+    let factorization = prime_factorization(&n);
+    assert!(start.elapsed() < Duration::from_secs(1), "took {:?}", start.elapsed());
+    assert_eq!(factorization, vec![(p.clone(), 1), (q.clone(), 1)]);
+
+    let divisors = get_factors_biguint(&n);
+    assert_eq!(divisors, vec![1u32.to_biguint().unwrap(), p, q, n]);
+}
+
+#[test]
+fn large_path_matches_direct_divisors() {
+    // 2^70 * 3 * 360 = 2^73 * 3^3 * 5 exceeds u64, so this exercises the non-sieve path
+    let n = BigUint::from(2u32).pow(70) * 3u32 * 360u32;
+    let divisors = get_factors_biguint(&n);
+    assert_eq!(divisors.len(), 74 * 4 * 2);
+    assert!(divisors.windows(2).all(|w| w[0] < w[1]));
+    assert!(divisors.iter().all(|d| (&n % d) == BigUint::from(0u32)));
+}
+
+#[test]
+fn factors_values_near_a_billion() {
+    // With a sieve sized to n these would need gigabytes; sqrt(n) keeps it tiny
+    let n = 1_000_000_000u64.to_biguint().unwrap();
+    assert_eq!(get_factors_biguint(&n).len(), 100);
+
+    let prime = 4_294_967_291u64.to_biguint().unwrap();
+    assert_eq!(get_factors_biguint(&prime), vec![1u32.to_biguint().unwrap(), prime]);
+
+    let n = 999_999_937u64.to_biguint().unwrap() * 360u32;
+    assert_eq!(get_factors_biguint(&n).len(), 24 * 2);
+}
+
+#[test]
+fn merged_base_factorization_matches_direct() {
+    for base in [30u64, 360, 2520] {
+        for m in [1u64, 2, 7, 360, 1_000_003, 4_294_967_311, 1 << 40] {
+            let m = m.to_biguint().unwrap();
+            assert_eq!(get_factors_of_scaled_base(&m, base), get_factors_biguint(&(&m * base)), "m={} base={}", m, base);
+        }
+    }
+}
+
+#[test]
+fn divisors_of_a_product_reuse_the_base_factorization() {
+    // 360 = 2^3 * 3^2 * 5, factored once and cached
+    let three_sixty = [(2u32, 3), (3, 2), (5, 1)].map(|(p, e)| (p.to_biguint().unwrap(), e));
+    assert_eq!(*base_factorization(360), three_sixty);
+    assert_eq!(factorize_biguint(&360u32.to_biguint().unwrap()), three_sixty);
+
+    let m: BigUint = "340282366920938463463374607431768211507".parse().unwrap();
+    for m in [1u32.to_biguint().unwrap(), 12u32.to_biguint().unwrap(), m] {
+        let divisors = divisors_of_product(&three_sixty, &m);
+        assert_eq!(divisors, get_factors_biguint(&(&m * 360u32)), "m={}", m);
+        assert_eq!(divisors, get_factors_of_scaled_base(&m, 360), "m={}", m);
+    }
+    assert_eq!(divisors_of_product(&[], &6u32.to_biguint().unwrap()), [1u32, 2, 3, 6].map(|d| d.to_biguint().unwrap()));
+}
+
+#[test]
+fn range_bounded_divisors_match_the_filtered_full_set() {
+    for m in [1u64, 2, 6, 360, 5040, 1_000_003, 1 << 40, 720_720_720] {
+        let m = m.to_biguint().unwrap();
+        let factorization = factorization_of_scaled_base(&m, 360);
+        let all = get_factors_of_scaled_base(&m, 360);
+        assert_eq!(divisor_count(&factorization), all.len() as u64, "m={}", m);
+
+        let range_start = (&m - 1u32) * 360u32;
+        for (low, high) in [(range_start.clone(), &m * 360u32), (range_start.clone() / 2u32, &range_start + 7u32), (0u32.into(), 0u32.into())] {
+            let expected: Vec<BigUint> = all.iter().filter(|d| **d >= low && **d <= high).cloned().collect();
+            assert_eq!(divisors_in_range(&factorization, &low, &high), expected, "m={} [{}, {}]", m, low, high);
+        }
+    }
+}
+
+#[test]
+fn formats_factorizations_as_products() {
+    assert_eq!(format_factorization(&prime_factorization(&BigUint::from(360u32))), "2^3 * 3^2 * 5");
+    assert_eq!(format_factorization(&prime_factorization(&BigUint::from(1_000_003u32))), "1000003");
+    assert_eq!(format_factorization(&prime_factorization(&BigUint::from(1u32))), "1");
+}
+
+#[test]
+fn factor_bases_factor_their_own_number() {
+    let m = BigUint::from(7u32);
+    for (factor_base, value) in [
+        (FactorBase::Linear, 2520u32),
+        (FactorBase::Affine { c: 1 }, 2521),
+        (FactorBase::Square, 2520 * 2520),
+    ] {
+        assert_eq!(factor_base.value(&m, 360), BigUint::from(value));
+        assert_eq!(factor_base.factorization(&m, 360), factorize_biguint(&BigUint::from(value)), "{}", factor_base);
+
+        let prepared = prepare_range(&m, &RangeParams { factor_base, ..RangeParams::new(360, 180) });
+        assert_eq!(prepared.factors_base, BigUint::from(value));
+        assert!(prepared.relevant_factors.iter().all(|f| (BigUint::from(value) % f) == BigUint::from(0u32)));
+    }
+
+    assert_eq!("affine:12".parse::<FactorBase>().unwrap(), FactorBase::Affine { c: 12 });
+    assert_eq!("square".parse::<FactorBase>().unwrap(), FactorBase::Square);
+    assert!("affine:-1".parse::<FactorBase>().is_err());
+}
+
+#[test]
+fn large_u64_squares_factor_without_a_sieve_to_their_root() {
+    // 4294967291 is the largest prime below 2^32; a sieve up to it takes seconds
+    let p = BigUint::from(4_294_967_291u64);
+    let n = &p * &p;
+    let start = Instant::now();
+    assert_eq!(factorize_biguint(&n), vec![(p.clone(), 2)]);
+    assert!(start.elapsed() < Duration::from_secs(1), "took {:?}", start.elapsed());
+
+    // The bound only moves work between trial division and rho, never the result
+    let numbers = [BigUint::from(720_720u32), BigUint::from(1_000_000_007u64 * 998_244_353), &n * 360u32];
+    let expected: Vec<_> = numbers.iter().map(factorize_biguint).collect();
+    for bound in [2, 97, DEFAULT_TRIAL_DIVISION_BOUND] {
+        set_trial_division_bound(bound);
+        assert_eq!(numbers.iter().map(factorize_biguint).collect::<Vec<_>>(), expected, "bound {}", bound);
+    }
+    set_trial_division_bound(u64::MAX);
+    assert_eq!(trial_division_bound(), MAX_TRIAL_DIVISION_BOUND);
+    set_trial_division_bound(DEFAULT_TRIAL_DIVISION_BOUND);
+}
+
+#[test]
+fn rho_gives_up_on_a_product_of_two_large_primes() {
+    // Two primes near 10^20 would take rho about 10^10 steps
+    let n: BigUint = "10000000000000000016800000000000000005031".parse().unwrap();
+    let start = Instant::now();
+    match try_factorize_biguint(&n, 100_000) {
+        Err(Error::FactoringBudget { n: named, max_rho_steps }) => assert_eq!((named, max_rho_steps), (n, 100_000)),
+        other => panic!("expected FactoringBudget, got {:?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5), "took {:?}", start.elapsed());
+
+    // Within the limit the result is the unlimited one
+    let semiprime = 1_000_000_007u64.to_biguint().unwrap() * "100000000000000000039".parse::<BigUint>().unwrap();
+    assert_eq!(try_prime_factorization(&semiprime, DEFAULT_MAX_RHO_STEPS).unwrap(), prime_factorization(&semiprime));
+}
+
+// x^2 + 1 needs about 437k steps to split this semiprime but x^2 + 2 only 26k,
+// so racing four polynomials wins even when the threads share one core
+#[cfg(not(feature = "serial"))]
+#[test]
+fn parallel_rho_beats_the_first_polynomial_on_a_hard_semiprime() {
+    let p = 14_187_859_757u64.to_biguint().unwrap();
+    let q = 1_071_970_496_227u64.to_biguint().unwrap();
+    let n = &p * &q;
+
+    let start = Instant::now();
+    let serial = primegen::factor::pollard_rho(&n);
+    let serial_time = start.elapsed();
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+    let start = Instant::now();
+    let parallel = pool.install(|| primegen::factor::pollard_rho_parallel(&n));
+    let parallel_time = start.elapsed();
+
+    for divisor in [&serial, &parallel] {
+        assert!(divisor == &p || divisor == &q, "{} is not a prime factor of {}", divisor, n);
+    }
+    assert!(parallel_time < serial_time, "parallel {:?} vs serial {:?}", parallel_time, serial_time);
+}
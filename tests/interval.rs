@@ -0,0 +1,86 @@
+use num_bigint::BigUint;
+use primegen::{
+    check_scaled_range, check_single_prime, format_report, primes_in_range_iter_with, Interval, PrimeGenOutcome, RangeParams,
+    Sampling, DEFAULT_SAMPLING_SEED,
+};
+
+fn primes(start: &BigUint, end: &BigUint, sampling: Sampling, interval: Interval) -> Vec<BigUint> {
+    primes_in_range_iter_with(start, end, sampling, DEFAULT_SAMPLING_SEED, interval, 100).collect()
+}
+
+fn small_primes(start: u32, end: u32, interval: Interval) -> Vec<BigUint> {
+    primes(&BigUint::from(start), &BigUint::from(end), Sampling::Auto, interval)
+}
+
+#[test]
+fn intervals_parse_and_write_their_brackets() {
+    assert_eq!("closed".parse::<Interval>().unwrap(), Interval::Closed);
+    assert_eq!("half-open".parse::<Interval>().unwrap(), Interval::HalfOpen);
+    assert!("open".parse::<Interval>().is_err());
+    assert_eq!(Interval::default(), Interval::HalfOpen);
+
+    let (start, end) = (BigUint::from(720u32), BigUint::from(1080u32));
+    assert_eq!(Interval::HalfOpen.format_range(&start, &end), "(720, 1080]");
+    assert_eq!(Interval::Closed.format_range(&start, &end), "[720, 1080]");
+    assert!(!Interval::HalfOpen.contains(&start, &start, &end));
+    assert!(Interval::Closed.contains(&start, &start, &end));
+}
+
+#[test]
+fn a_prime_at_range_end_is_included_either_way() {
+    for interval in [Interval::HalfOpen, Interval::Closed] {
+        assert_eq!(small_primes(1, 7, interval), [2u32, 3, 5, 7].map(BigUint::from));
+    }
+}
+
+#[test]
+fn closed_ranges_take_a_prime_start_when_sieved() {
+    assert_eq!(small_primes(7, 14, Interval::HalfOpen), [11u32, 13].map(BigUint::from));
+    assert_eq!(small_primes(7, 14, Interval::Closed), [7u32, 11, 13].map(BigUint::from));
+}
+
+#[test]
+fn closed_ranges_take_a_prime_start_beyond_u64() {
+    let start: BigUint = "18446744073709551629".parse().unwrap(); // 2^64 + 13
+    let end = &start + 20u32;
+    assert!(primes(&start, &end, Sampling::Auto, Interval::HalfOpen).is_empty());
+    assert_eq!(primes(&start, &end, Sampling::Auto, Interval::Closed), std::slice::from_ref(&start));
+}
+
+#[test]
+fn closed_ranges_take_a_prime_start_when_sampled() {
+    // Ten blocks of one odd number, each two wide, leave the RNG no slack, so
+    // the first block starts right after the number below the range
+    let start: BigUint = "18446744073709551629".parse().unwrap(); // 2^64 + 13
+    let end = &start + 22u32;
+    let sampling = Sampling::Sample(10);
+    assert!(sampling.applies_to(&start, &end) && sampling.applies_to(&(&start - 1u32), &end));
+
+    assert!(!primes(&start, &end, sampling, Interval::HalfOpen).contains(&start));
+    assert_eq!(primes(&start, &end, sampling, Interval::Closed).first(), Some(&start));
+}
+
+#[test]
+fn closed_scales_check_and_report_their_start() {
+    let m = BigUint::from(2u32);
+    for (interval, primes_in_range, range) in [(Interval::HalfOpen, 2, "(7, 14]"), (Interval::Closed, 3, "[7, 14]")] {
+        let params = RangeParams { interval, ..RangeParams::new(7, 7) };
+        let report = check_scaled_range(&m, &params, 1_000, None);
+        assert_eq!((report.primes_in_range, report.interval), (primes_in_range, interval));
+        assert!(format_report(&report).contains(&format!("Range {}", range)));
+        assert_eq!(check_single_prime(&BigUint::from(7u32), &m, &params).in_range, interval == Interval::Closed);
+    }
+}
+
+#[test]
+fn closed_scales_report_sampling_as_generated() {
+    // (21, 42] holds 21 numbers, too few for a sample of 10 odd ones, while
+    // [21, 42] holds 22
+    let m = BigUint::from(2u32);
+    for (interval, sampled) in [(Interval::HalfOpen, false), (Interval::Closed, true)] {
+        let params = RangeParams { interval, sampling: Sampling::Sample(10), ..RangeParams::new(21, 42) };
+        let report = check_scaled_range(&m, &params, 1_000, None);
+        assert_eq!(report.sampled, sampled, "{}", interval);
+        assert_eq!(matches!(report.prime_gen_outcome, PrimeGenOutcome::Sampled { .. }), sampled, "{}", interval);
+    }
+}
@@ -0,0 +1,101 @@
+use num_bigint::BigUint;
+use primal::Sieve;
+use primegen::{
+    check_primes_against_range, check_scaled_range, is_primality_proven, is_prime_u64_deterministic, scale_of, PrimeCountCheck,
+    RangeParams, RunSummary, Sampling, DEFAULT_BASE,
+};
+
+#[test]
+fn agrees_with_the_sieve_below_a_million() {
+    let sieve = Sieve::new(1_000_000);
+    for n in 0..1_000_000u64 {
+        assert_eq!(is_prime_u64_deterministic(n), sieve.is_prime(n as usize), "n={}", n);
+    }
+}
+
+#[test]
+fn known_primes() {
+    let primes = [
+        2, 3, 37, 41, 1_000_000_007, 2_147_483_647, 4_294_967_291, 999_999_999_989,
+        2_305_843_009_213_693_951, // 2^61 - 1
+        18_446_744_073_709_551_557, // the largest u64 prime
+    ];
+    for p in primes {
+        assert!(is_prime_u64_deterministic(p), "{}", p);
+    }
+}
+
+#[test]
+fn rejects_carmichael_numbers_and_strong_pseudoprimes() {
+    let carmichael = [561, 1105, 1729, 2465, 2821, 6601, 8911, 41_041, 825_265, 321_197_185, 5_394_826_801, 232_250_619_601];
+    let strong_pseudoprimes = [
+        2047,                      // base 2
+        1_373_653,                 // bases 2 and 3
+        3_215_031_751,             // bases 2, 3, 5 and 7
+        2_152_302_898_747,         // bases 2 through 11
+        3_474_749_660_383,         // bases 2 through 13
+        341_550_071_728_321,       // bases 2 through 17
+        3_825_123_056_546_413_051, // bases 2 through 23
+    ];
+    for n in carmichael.into_iter().chain(strong_pseudoprimes) {
+        assert!(!is_prime_u64_deterministic(n), "{}", n);
+    }
+    assert!(!is_prime_u64_deterministic(u64::MAX));
+    assert!(!is_prime_u64_deterministic(4_294_967_291 * 4_294_967_279));
+}
+
+#[test]
+fn verification_agrees_across_the_sieve_boundary() {
+    // 10,000,000 lies in m = 27778, where sieve lookups give way to Miller-Rabin
+    let params = RangeParams { verify: true, ..RangeParams::new(DEFAULT_BASE, 180) };
+    for m in [1, 2, 27_778, 27_779u32].map(BigUint::from) {
+        let report = check_scaled_range(&m, &params, 100_000, None);
+        let verification = report.verification.expect("verify was set");
+        assert!(verification.disagreements.is_empty(), "m={}: {:?}", m, verification.disagreements);
+        assert_eq!(verification.primes_verified, report.total_checked, "m={}", m);
+        assert!(verification.composites_verified > 0 && verification.composites_verified < report.total_checked, "m={}", m);
+    }
+    assert!(check_scaled_range(&BigUint::from(1u32), &RangeParams::new(DEFAULT_BASE, 180), 100_000, None).verification.is_none());
+}
+
+#[test]
+fn prime_counts_are_sanity_checked_against_li() {
+    let params = RangeParams { sanity_check: true, ..RangeParams::new(DEFAULT_BASE, 180) };
+    for m in [1, 2, 27_778, 1_000_000u32].map(BigUint::from) {
+        let check = check_scaled_range(&m, &params, 100_000, None).prime_count_check.expect("sanity_check was set");
+        assert!(check.within_tolerance, "m={}: {:?}", m, check);
+    }
+
+    // The first million holds 78,498 primes; losing a tenth of them is flagged, as is
+    // finding none in the next hundred scales
+    let (start, end) = (BigUint::from(0u32), BigUint::from(1_000_000u32));
+    assert!(PrimeCountCheck::new(78_498, &start, &end).within_tolerance);
+    assert!(!PrimeCountCheck::new(70_000, &start, &end).within_tolerance);
+    assert!(!PrimeCountCheck::new(0, &BigUint::from(1_000_000u32), &BigUint::from(1_036_000u32)).within_tolerance);
+
+    // Sampled ranges hold only part of their primes, so they are not compared
+    let sampled = RangeParams { sampling: Sampling::Sample(100), ..params };
+    assert!(check_scaled_range(&BigUint::from(1_000u32), &sampled, 100_000, None).prime_count_check.is_none());
+}
+
+#[test]
+fn primes_beyond_u64_count_as_only_probable() {
+    let two_64 = BigUint::from(u64::MAX) + 1u32;
+    assert!(is_primality_proven(&BigUint::from(u64::MAX)));
+    assert!(!is_primality_proven(&two_64));
+
+    // With base 1000 the scale holding 2^64 has 13 proven primes below it and 8 probable ones above
+    let params = RangeParams::new(1000, 180);
+    let report = check_scaled_range(&scale_of(&two_64, 1000), &params, 100_000, None);
+    assert_eq!((report.proven_primes, report.probable_primes), (13, 8));
+    assert_eq!(report.proven_primes + report.probable_primes, report.total_checked);
+
+    let mut summary = RunSummary::default();
+    summary.record(&report);
+    summary.record(&check_scaled_range(&BigUint::from(1u32), &params, 100_000, None));
+    assert_eq!((summary.proven_primes, summary.probable_primes), (13 + 168, 8));
+
+    // Primes handed in from elsewhere are never tested, so they are neither
+    let given = check_primes_against_range(&[BigUint::from(181u32)], &BigUint::from(1u32), &RangeParams::new(DEFAULT_BASE, 180));
+    assert_eq!((given.total_checked, given.proven_primes, given.probable_primes), (1, 0, 0));
+}
@@ -0,0 +1,179 @@
+use num_bigint::{BigInt, BigUint, ToBigUint};
+use primegen::{
+    abs_diff, check_proximity_biguint, check_proximity_combined, check_proximity_directed, merge_candidates, nearest_candidate,
+    nearest_candidate_directed, nearest_candidates_sweep, nearest_merged, primes_near_candidates, signed_offset,
+    CandidateSource, Direction,
+};
+
+// Small deterministic generator so the test needs no extra dependencies
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+}
+
+fn linear_nearest(prime: &BigUint, candidates: &[BigUint]) -> Option<(BigUint, BigUint)> {
+    candidates.iter()
+        .map(|c| (abs_diff(prime, c), c.clone()))
+        .min()
+        .map(|(diff, c)| (c, diff))
+}
+
+#[test]
+fn binary_search_matches_linear_scan() {
+    let mut rng = Lcg(360);
+    for _ in 0..500 {
+        let len = rng.next(40) as usize;
+        let mut candidates: Vec<BigUint> = (0..len).map(|_| rng.next(2_000).to_biguint().unwrap()).collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let prime = rng.next(2_200).to_biguint().unwrap();
+        let k = rng.next(200).to_biguint().unwrap();
+        let expected = linear_nearest(&prime, &candidates);
+
+        assert_eq!(nearest_candidate(&prime, &candidates), expected, "{} in {:?}", prime, candidates);
+        assert_eq!(
+            check_proximity_biguint(&prime, &candidates, &k),
+            expected.is_some_and(|(_, diff)| diff <= k)
+        );
+    }
+}
+
+#[test]
+fn proximity_is_inclusive_at_k() {
+    let candidates = vec![100u32.to_biguint().unwrap(), 200u32.to_biguint().unwrap()];
+    let k = 10u32.to_biguint().unwrap();
+    assert!(check_proximity_biguint(&110u32.to_biguint().unwrap(), &candidates, &k));
+    assert!(!check_proximity_biguint(&111u32.to_biguint().unwrap(), &candidates, &k));
+    assert!(check_proximity_biguint(&190u32.to_biguint().unwrap(), &candidates, &k));
+    assert!(!check_proximity_biguint(&5u32.to_biguint().unwrap(), &[], &k));
+}
+
+#[test]
+fn prime_counts_near_candidates_match_a_linear_scan() {
+    let mut rng = Lcg(181);
+    for _ in 0..200 {
+        let mut primes: Vec<BigUint> = (0..rng.next(60)).map(|_| rng.next(2_000).to_biguint().unwrap()).collect();
+        primes.sort();
+        primes.dedup();
+        let candidates: Vec<BigUint> = (0..rng.next(20)).map(|_| rng.next(2_200).to_biguint().unwrap()).collect();
+        let k = rng.next(200).to_biguint().unwrap();
+
+        let expected: Vec<usize> = candidates.iter()
+            .map(|c| primes.iter().filter(|p| linear_nearest(p, std::slice::from_ref(c)).unwrap().1 <= k).count())
+            .collect();
+        assert_eq!(primes_near_candidates(&candidates, &primes, &k), expected);
+    }
+
+    // Inclusive at exactly k on both sides, and k = 0 counts only the candidate itself
+    let primes: Vec<BigUint> = [89u32, 97, 101, 103, 110, 111].iter().map(|p| p.to_biguint().unwrap()).collect();
+    let hundred = [100u32.to_biguint().unwrap()];
+    assert_eq!(primes_near_candidates(&hundred, &primes, &10u32.to_biguint().unwrap()), vec![4]);
+    assert_eq!(primes_near_candidates(&[97u32.to_biguint().unwrap()], &primes, &0u32.to_biguint().unwrap()), vec![1]);
+}
+
+#[test]
+fn combined_check_matches_the_closer_of_both_methods() {
+    let big = |n: u32| n.to_biguint().unwrap();
+    let merged = merge_candidates(&[big(2), big(5), big(9)], &[big(5), big(7)]);
+    let sources: Vec<_> = merged.iter().map(|(c, source)| (c.clone(), *source)).collect();
+    assert_eq!(sources, [
+        (big(2), CandidateSource::Factor),
+        (big(5), CandidateSource::Both),
+        (big(7), CandidateSource::Sequence),
+        (big(9), CandidateSource::Factor),
+    ]);
+    assert_eq!(nearest_merged(&big(8), &merged), Some((big(7), big(1), CandidateSource::Sequence)));
+    assert_eq!(check_proximity_combined(&big(13), &merged, &big(3)), None);
+    assert_eq!(nearest_merged(&big(1), &[]), None);
+
+    let mut rng = Lcg(180);
+    for _ in 0..500 {
+        let mut sets: Vec<Vec<BigUint>> = (0..2)
+            .map(|_| (0..rng.next(20)).map(|_| rng.next(1_000).to_biguint().unwrap()).collect())
+            .collect();
+        sets.iter_mut().for_each(|set| { set.sort(); set.dedup(); });
+        let (factors, seq_terms) = (&sets[0], &sets[1]);
+        let merged = merge_candidates(factors, seq_terms);
+        assert!(merged.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let prime = rng.next(1_100).to_biguint().unwrap();
+        let k = rng.next(100).to_biguint().unwrap();
+        let by_method = [nearest_candidate(&prime, factors), nearest_candidate(&prime, seq_terms)];
+        let shortest = by_method.iter().flatten().map(|(_, diff)| diff.clone()).min();
+
+        let combined = nearest_merged(&prime, &merged);
+        assert_eq!(combined.as_ref().map(|(_, diff, _)| diff.clone()), shortest);
+        assert_eq!(combined, linear_nearest(&prime, &merged.iter().map(|(c, _)| c.clone()).collect::<Vec<_>>())
+            .map(|(c, diff)| {
+                let source = merged.iter().find(|(m, _)| m == &c).unwrap().1;
+                (c, diff, source)
+            }));
+        if let Some((candidate, _, source)) = &combined {
+            let (in_factors, in_seq) = (factors.contains(candidate), seq_terms.contains(candidate));
+            let expected = match (in_factors, in_seq) {
+                (true, true) => CandidateSource::Both,
+                (true, false) => CandidateSource::Factor,
+                _ => CandidateSource::Sequence,
+            };
+            assert_eq!(*source, expected);
+        }
+        assert_eq!(check_proximity_combined(&prime, &merged, &k).is_some(), shortest.is_some_and(|diff| diff <= k));
+    }
+}
+
+#[test]
+fn directed_checks_only_count_candidates_on_one_side() {
+    let big = |n: u32| n.to_biguint().unwrap();
+    let candidates = [big(100), big(200)];
+    let nearest = |prime, direction| nearest_candidate_directed(&big(prime), &candidates, direction);
+
+    // Equidistant between two candidates, each side sees its own
+    assert_eq!(nearest(150, Direction::Above), Some((big(100), big(50))));
+    assert_eq!(nearest(150, Direction::Below), Some((big(200), big(50))));
+    assert_eq!(nearest(150, Direction::Both), Some((big(100), big(50))));
+
+    // A prime on a candidate lies on both sides of it
+    assert_eq!(nearest(200, Direction::Above), Some((big(200), big(0))));
+    assert_eq!(nearest(200, Direction::Below), Some((big(200), big(0))));
+    // Past the outermost candidates one side has nothing
+    assert_eq!(nearest(99, Direction::Above), None);
+    assert_eq!(nearest(201, Direction::Below), None);
+
+    // Exactly k away on the allowed side is covered, one further is not
+    assert!(check_proximity_directed(&big(120), &candidates, &big(20), Direction::Above));
+    assert!(!check_proximity_directed(&big(121), &candidates, &big(20), Direction::Above));
+    assert!(check_proximity_directed(&big(180), &candidates, &big(20), Direction::Below));
+    assert!(!check_proximity_directed(&big(179), &candidates, &big(20), Direction::Below));
+    // Just past a candidate on the wrong side does not count, however close
+    assert!(!check_proximity_directed(&big(99), &candidates, &big(20), Direction::Above));
+    assert!(!check_proximity_directed(&big(201), &candidates, &big(20), Direction::Below));
+
+    assert_eq!(signed_offset(&big(1801), &big(1981)), BigInt::from(-180));
+    assert_eq!(signed_offset(&big(1981), &big(1801)), BigInt::from(180));
+}
+
+#[test]
+fn sweeps_find_the_same_nearest_candidates_as_binary_search() {
+    let big = |n: u32| n.to_biguint().unwrap();
+    // Candidates on and between the numbers, with ties, repeats and numbers past both ends
+    let candidates: Vec<_> = [10, 20, 30, 35, 36, 60, 100].map(big).to_vec();
+    let numbers: Vec<_> = [0, 5, 10, 10, 15, 25, 33, 35, 36, 37, 48, 80, 100, 101, 500].map(big).to_vec();
+    for direction in [Direction::Both, Direction::Above, Direction::Below] {
+        let expected: Vec<_> = numbers.iter().map(|n| nearest_candidate_directed(n, &candidates, direction)).collect();
+        assert_eq!(nearest_candidates_sweep(&numbers, &candidates, direction), expected, "{}", direction);
+        assert!(nearest_candidates_sweep(&numbers, &[], direction).iter().all(Option::is_none));
+    }
+
+    // Every number up to 2,000 against the factors of 5,040
+    let factors: Vec<_> = (1..=5040u32).filter(|d| 5040 % d == 0).map(big).collect();
+    let numbers: Vec<_> = (0..2000u32).map(big).collect();
+    for direction in [Direction::Both, Direction::Above, Direction::Below] {
+        let expected: Vec<_> = numbers.iter().map(|n| nearest_candidate_directed(n, &factors, direction)).collect();
+        assert_eq!(nearest_candidates_sweep(&numbers, &factors, direction), expected, "{}", direction);
+    }
+}
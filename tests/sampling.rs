@@ -0,0 +1,145 @@
+use num_bigint::BigUint;
+use num_integer::Integer;
+use primegen::{
+    check_scaled_range, ensure_exhaustive, evenly_spaced, generate_primes_in_range, generate_primes_in_range_with,
+    is_sampled_range, sample_candidates, sample_odd_numbers, sample_odd_numbers_with, sampling_rng, stratified_sample, RangeParams,
+    Sampling, DEFAULT_BASE, DEFAULT_SAMPLING_SEED, MAX_K,
+};
+
+#[test]
+fn samples_spread_across_the_whole_range_deterministically() {
+    // Just past u64, and far wider than the sampling threshold
+    let start: BigUint = "18446744073709551616".parse().unwrap();
+    let end = &start + 1_000_000_000u64;
+    assert!(is_sampled_range(&start, &end));
+
+    let candidates = sample_candidates(&start, &end);
+    assert_eq!(candidates.len(), 1_000_000);
+    assert!(candidates.iter().all(|n| n.is_odd() && n >= &start && n <= &end));
+
+    // Every tenth of the range gets its share, not just the bottom
+    let tenth = (&end - &start) / 10u32;
+    for i in 0..10u32 {
+        let lo = &start + &tenth * i;
+        let hi = &lo + &tenth;
+        let in_tenth = candidates.iter().filter(|n| *n >= &lo && *n < &hi).count();
+        assert_eq!(in_tenth, 100_000, "tenth {}", i);
+    }
+
+    assert_eq!(candidates, sample_candidates(&start, &end));
+}
+
+#[test]
+fn ranges_within_the_sieve_are_exhaustive() {
+    let start = BigUint::from(0u32);
+    let end = BigUint::from(10_000_000u32);
+    assert!(!is_sampled_range(&start, &end));
+}
+
+#[test]
+fn explicit_samples_have_the_requested_size_and_spread() {
+    let start = BigUint::from(0u32);
+    let end = BigUint::from(360_000u32);
+    assert!(Sampling::Sample(500).applies_to(&start, &end));
+    assert!(!Sampling::Sample(180_000).applies_to(&start, &end));
+
+    let candidates = sample_odd_numbers(&start, &end, 500);
+    assert_eq!(candidates.len(), 500);
+    assert!(candidates.windows(2).all(|w| w[0] < w[1] && w[0].is_odd()));
+    assert!(candidates.last().unwrap() > &(&end / 2u32));
+
+    // No prime of a sample lies outside it
+    let primes = generate_primes_in_range_with(&start, &end, Sampling::Sample(500));
+    assert!(!primes.is_empty() && primes.iter().all(|p| candidates.contains(p)));
+}
+
+#[test]
+fn exhaustive_mode_never_samples() {
+    let start: BigUint = "18446744073709551616".parse().unwrap();
+    let end = &start + 10_000_000u64;
+    assert!(is_sampled_range(&start, &end));
+    assert!(!Sampling::Exhaustive.applies_to(&start, &end));
+
+    // A range that auto mode would sample is refused rather than enumerated
+    let m = BigUint::from(u64::MAX / 10_000_000 + 2);
+    assert!(ensure_exhaustive(&m, 10_000_000).is_err());
+    assert!(ensure_exhaustive(&m, 360).is_ok());
+    assert!(ensure_exhaustive(&BigUint::from(1_000_000u32), 10_000_000).is_ok());
+}
+
+#[test]
+fn sampled_ranges_beyond_u64_find_primes() {
+    let start: BigUint = "18446744073709551616".parse().unwrap();
+    let end = &start + 1_000_000_000u64;
+    let primes = generate_primes_in_range_with(&start, &end, Sampling::Sample(2_000));
+    // About one odd number in 22 is prime at this size
+    assert!(primes.len() > 40 && primes.len() < 150, "{} primes", primes.len());
+    assert!(primes.iter().all(|p| p > &start && p <= &end));
+}
+
+#[test]
+fn capped_checks_spread_over_the_whole_range() {
+    assert_eq!(evenly_spaced(0..9, 9, 3).collect::<Vec<_>>(), [1, 4, 7]);
+    assert_eq!(evenly_spaced(0..5, 5, 10).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    assert_eq!(evenly_spaced(0..5, 5, 0).count(), 0);
+
+    // Scale 1000 holds 20 primes in (359640, 360000]; a cap of 10 must not keep only the smallest
+    let m = BigUint::from(1000u32);
+    for chunk_size in [3, 10_000] {
+        let params = RangeParams { chunk_size, ..RangeParams::new(DEFAULT_BASE, MAX_K) };
+        let report = check_scaled_range(&m, &params, 10, None);
+        let all = generate_primes_in_range(&report.range_start, &report.range_end);
+        assert_eq!((report.total_checked, report.primes_in_range, all.len()), (10, 20, 20));
+
+        let checked: Vec<_> = report.attribution.iter().map(|a| a.prime.clone()).collect();
+        let mut rng = sampling_rng(DEFAULT_SAMPLING_SEED, &report.range_start);
+        assert_eq!(checked, stratified_sample(all.iter().cloned(), all.len(), 10, &mut rng).collect::<Vec<_>>());
+        // One prime of each of ten runs of two, up to the top of the range
+        assert!(checked.iter().enumerate().all(|(i, prime)| all[2 * i..2 * i + 2].contains(prime)));
+    }
+}
+
+#[test]
+fn samples_replay_with_their_seed() {
+    let start: BigUint = "18446744073709551616".parse().unwrap();
+    let end = &start + 1_000_000_000u64;
+    let sample = |seed| sample_odd_numbers_with(&start, &end, 5_000, seed);
+    assert_eq!(sample(7), sample(7));
+    assert_ne!(sample(7), sample(8));
+
+    // A different seed moves the blocks within their stretch of the range, not out of it
+    let stride = (&end - &start) / 1_000u32;
+    for seed in [7, 8] {
+        let candidates = sample(seed);
+        assert_eq!(candidates.len(), 5_000);
+        for block in 0..1_000u32 {
+            let lo = &start + &stride * block;
+            let hi = &lo + &stride;
+            assert_eq!(candidates.iter().filter(|n| *n > &lo && *n <= &hi).count(), 5, "block {}", block);
+        }
+    }
+
+    let mut rng = sampling_rng(3, &start);
+    let picked: Vec<usize> = stratified_sample(0..9, 9, 3, &mut rng).collect();
+    assert!(picked.iter().enumerate().all(|(j, &i)| i / 3 == j));
+    assert_eq!(stratified_sample(0..5, 5, 10, &mut rng).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn scans_with_different_seeds_run_side_by_side() {
+    // Scale 1000 holds 20 primes, so a cap of 10 has each seed pick its own
+    let m = BigUint::from(1000u32);
+    let checked = |sampling_seed| {
+        let params = RangeParams { sampling_seed, ..RangeParams::new(DEFAULT_BASE, MAX_K) };
+        let report = check_scaled_range(&m, &params, 10, None);
+        report.attribution.iter().map(|a| a.prime.clone()).collect::<Vec<_>>()
+    };
+    let (first, second) = std::thread::scope(|scope| {
+        let first = scope.spawn(|| checked(7));
+        let second = scope.spawn(|| checked(8));
+        (first.join().unwrap(), second.join().unwrap())
+    });
+    assert_eq!(first, checked(7));
+    assert_eq!(second, checked(8));
+    assert_ne!(first, second);
+}
@@ -0,0 +1,82 @@
+use num_bigint::BigUint;
+use primegen::{
+    check_scaled_range, nearest_candidate, prepare_range, recursive_sequence_generator_optimized, RangeParams, Sequence, TermWindow,
+    DEFAULT_BASE, MAX_K,
+};
+use std::sync::Arc;
+
+fn big(values: &[u64]) -> Vec<BigUint> {
+    values.iter().map(|&v| BigUint::from(v)).collect()
+}
+
+#[test]
+fn triangular_default_keeps_the_original_terms() {
+    let terms = Sequence::default().terms(&BigUint::from(181u32), &BigUint::from(540u32));
+    assert_eq!(terms[..6], big(&[181, 183, 186, 190, 195, 201]));
+    assert_eq!(terms.last(), Some(&BigUint::from(531u32)));
+    assert_eq!(terms, recursive_sequence_generator_optimized(&BigUint::from(181u32), &BigUint::from(540u32)));
+}
+
+#[test]
+fn arithmetic_and_custom_steps() {
+    let arithmetic = Sequence::Arithmetic { step: BigUint::from(7u32) };
+    assert_eq!(arithmetic.terms(&BigUint::from(10u32), &BigUint::from(40u32)), big(&[10, 17, 24, 31, 38]));
+
+    // Quadratic offsets: n_i = first + (i - 1)^2
+    let squares = Sequence::Custom(Arc::new(|term, i| term + (2 * i - 1)));
+    assert_eq!(squares.terms(&BigUint::from(100u32), &BigUint::from(125u32)), big(&[100, 101, 104, 109, 116, 125]));
+}
+
+#[test]
+fn sequences_that_stop_growing_end() {
+    let stuck = Sequence::Arithmetic { step: BigUint::from(0u32) };
+    assert_eq!(stuck.terms(&BigUint::from(5u32), &BigUint::from(50u32)), big(&[5]));
+    assert!(stuck.terms(&BigUint::from(51u32), &BigUint::from(50u32)).is_empty());
+}
+
+#[test]
+fn reports_name_the_sequence_used() {
+    let params = RangeParams { sequence: Sequence::Arithmetic { step: BigUint::from(30u32) }, ..RangeParams::new(DEFAULT_BASE, MAX_K) };
+    let report = check_scaled_range(&BigUint::from(3u32), &params, 100_000, None);
+    assert_eq!(report.sequence, "+30");
+    assert_eq!(report.seq_terms, (1080 + 180 - 901) / 30 + 1);
+}
+
+#[test]
+fn streamed_terms_match_the_collected_ones() {
+    let first = BigUint::from(1_000_181u32);
+    let max_value = BigUint::from(1_200_000u32);
+    let triangular = Sequence::default();
+    let collected = triangular.terms(&first, &max_value);
+    assert_eq!(triangular.terms_iter(&first, &max_value).collect::<Vec<_>>(), collected);
+
+    // Windows over ascending spans give every number the same nearest term as the full list
+    for width in [1u32, 7, 100, 5_000] {
+        let mut window = TermWindow::new(triangular.terms_iter(&first, &max_value));
+        let mut low = BigUint::from(1_000_000u32);
+        while low < BigUint::from(1_210_000u32) {
+            let high = &low + width;
+            let terms = window.covering(&low, &high);
+            assert!(terms.len() <= collected.len());
+            for n in [low.clone(), &low + width / 2, high.clone()] {
+                assert_eq!(nearest_candidate(&n, &terms), nearest_candidate(&n, &collected), "{} with width {}", n, width);
+            }
+            low = high;
+        }
+        assert_eq!(window.finish(), collected.len());
+    }
+
+    let untouched = TermWindow::new(triangular.terms_iter(&first, &max_value));
+    assert_eq!(untouched.finish(), collected.len());
+}
+
+#[test]
+fn consecutive_scales_generate_disjoint_terms() {
+    let params = RangeParams::new(DEFAULT_BASE, MAX_K);
+    let scales: Vec<_> = (1..=50u32).map(|m| prepare_range(&BigUint::from(m), &params)).collect();
+    for pair in scales.windows(2) {
+        let (this, next) = (&pair[0], &pair[1]);
+        assert_eq!(this.seq_terms, params.sequence.terms(&this.seq_base, &(&this.range_end + MAX_K)));
+        assert!(this.seq_terms.last().is_some_and(|last| last < &next.seq_base), "scale ending at {}", this.range_end);
+    }
+}
@@ -0,0 +1,116 @@
+use primal::Sieve;
+use num_bigint::BigUint;
+use num_integer::Roots;
+use num_traits::ToPrimitive;
+use primegen::sieve::{cache_base_primes, segmented_sieve, segments_with};
+use primegen::{
+    generate_primes_in_range, is_prime_biguint, primes_in_range_iter, primes_in_range_iter_with, range_bounds,
+    sample_odd_numbers, Interval, Sampling, DEFAULT_BASE, DEFAULT_SAMPLING_SEED,
+};
+use std::sync::Arc;
+
+fn primal_primes(start: u64, end: u64) -> Vec<u64> {
+    Sieve::new(end as usize + 1)
+        .primes_from(0)
+        .map(|p| p as u64)
+        .skip_while(|&p| p < start)
+        .take_while(|&p| p <= end)
+        .collect()
+}
+
+#[test]
+fn matches_primal_over_several_ranges() {
+    // Tiny ranges, ranges straddling segment boundaries and one spanning many segments
+    let ranges = [
+        (0, 0), (0, 1), (0, 2), (2, 2), (3, 100), (1, 1000), (24, 30),
+        (262_000, 262_200), (262_144, 524_288), (999_000, 1_001_000), (0, 3_000_000),
+    ];
+    for (start, end) in ranges {
+        assert_eq!(segmented_sieve(start, end), primal_primes(start, end), "range [{}, {}]", start, end);
+    }
+}
+
+#[test]
+fn matches_primality_test_far_from_zero() {
+    let start = 1_000_000_000_000u64;
+    let end = start + 1_000_000;
+    let expected: Vec<u64> = (start..=end).filter(|&n| primal::is_prime(n)).collect();
+    assert_eq!(segmented_sieve(start, end), expected);
+}
+
+#[test]
+fn handles_the_top_of_u64() {
+    let end = u64::MAX;
+    let start = end - 1_000;
+    let expected: Vec<u64> = (start..=end).filter(|&n| primal::is_prime(n)).collect();
+    assert_eq!(segmented_sieve(start, end), expected);
+    assert!(segmented_sieve(10, 5).is_empty());
+}
+
+#[test]
+fn prime_iterator_matches_the_collected_primes_and_is_lazy() {
+    let start = BigUint::from(1_000_000u32);
+    let end = BigUint::from(2_000_000u32);
+    assert_eq!(primes_in_range_iter(&start, &end).collect::<Vec<_>>(), generate_primes_in_range(&start, &end));
+
+    // Only the first segment of a range wider than memory is ever sieved
+    let start = BigUint::from(1_000_000_000_000_000u64);
+    let end = &start + 1_000_000_000_000u64;
+    let first: Vec<BigUint> = primes_in_range_iter_with(&start, &end, Sampling::Exhaustive, DEFAULT_SAMPLING_SEED, Interval::HalfOpen, 10_000).take(3).collect();
+    assert_eq!(first, ["1000000000000037", "1000000000000091", "1000000000000159"].map(|p| p.parse::<BigUint>().unwrap()));
+}
+
+#[test]
+fn chunk_size_does_not_change_the_primes_found() {
+    // Sampled candidates are tested by the chunked primality tester rather than the sieve
+    let start = BigUint::from(1_000_000u32);
+    let end = BigUint::from(3_000_000u32);
+    let unchunked: Vec<BigUint> = sample_odd_numbers(&start, &end, 20_000).into_iter().filter(is_prime_biguint).collect();
+    assert!(!unchunked.is_empty());
+    for chunk_size in [1, 7, 1_000, 20_000, 1_000_000] {
+        let chunked: Vec<BigUint> = primes_in_range_iter_with(&start, &end, Sampling::Sample(20_000), DEFAULT_SAMPLING_SEED, Interval::HalfOpen, chunk_size).collect();
+        assert_eq!(chunked, unchunked, "chunk size {}", chunk_size);
+    }
+}
+
+#[test]
+fn every_path_excludes_a_prime_at_the_range_start() {
+    // The sieve below u64::MAX, the odd-number scan beyond it and a sample
+    let beyond_u64 = (BigUint::from(1u32) << 64) + 13u32;
+    assert!(is_prime_biguint(&beyond_u64));
+    for (prime, sampling) in [
+        (BigUint::from(1_000_003u32), Sampling::Exhaustive),
+        (beyond_u64.clone(), Sampling::Exhaustive),
+        (beyond_u64.clone(), Sampling::Sample(10)),
+    ] {
+        let end = &prime + 1_000u32;
+        let from_prime: Vec<BigUint> = primes_in_range_iter_with(&prime, &end, sampling, DEFAULT_SAMPLING_SEED, Interval::HalfOpen, 100).collect();
+        assert!(!from_prime.contains(&prime), "{} at the start of ({}, {}] with {}", prime, prime, end, sampling);
+        assert!(from_prime.iter().all(|p| p > &prime && p <= &end));
+
+        let before_prime = &prime - 1u32;
+        let through_prime: Vec<BigUint> = primes_in_range_iter_with(&before_prime, &prime, sampling, DEFAULT_SAMPLING_SEED, Interval::HalfOpen, 100).collect();
+        assert_eq!(through_prime, vec![prime.clone()], "({}, {}] with {}", before_prime, prime, sampling);
+    }
+    assert!(sample_odd_numbers(&beyond_u64, &(&beyond_u64 + 100u32), 5).iter().all(|n| n > &beyond_u64));
+}
+
+#[test]
+fn cached_base_primes_suffice_for_the_largest_scale() {
+    let scale_bounds = |m: u32| {
+        let (start, end) = range_bounds(&BigUint::from(m), DEFAULT_BASE);
+        (start.to_u64().unwrap(), end.to_u64().unwrap())
+    };
+    let (_, top_end) = scale_bounds(50_000);
+    let base = cache_base_primes(top_end);
+    assert!(base.covers(top_end));
+    assert_eq!(base.primes(), primal_primes(0, top_end.sqrt()));
+
+    // Smaller scales reuse the same list rather than sieving their own
+    assert!(Arc::ptr_eq(&base, &cache_base_primes(scale_bounds(1_000).1)));
+    for m in [1, 2, 1_000, 49_999, 50_000] {
+        let (start, end) = scale_bounds(m);
+        let primes: Vec<u64> = segments_with(start + 1, end, Arc::clone(&base)).flatten().collect();
+        assert_eq!(primes, primal_primes(start + 1, end), "scale {}", m);
+    }
+}
@@ -0,0 +1,41 @@
+use num_bigint::BigUint;
+use primegen::{abs_diff, ceil_div, isqrt, SaturatingSub};
+
+fn big(n: u64) -> BigUint {
+    BigUint::from(n)
+}
+
+#[test]
+fn abs_diff_is_symmetric() {
+    assert_eq!(abs_diff(&big(10), &big(3)), big(7));
+    assert_eq!(abs_diff(&big(3), &big(10)), big(7));
+    // Equal inputs are zero apart, whichever branch takes them
+    assert_eq!(abs_diff(&big(42), &big(42)), big(0));
+    assert_eq!(abs_diff(&big(0), &big(0)), big(0));
+    assert_eq!(abs_diff(&big(0), &big(u64::MAX)), big(u64::MAX));
+    assert_eq!(big(3).saturating_sub(&big(10)), big(0));
+}
+
+#[test]
+fn ceil_div_rounds_up_for_both_widths() {
+    assert_eq!(ceil_div(&10u64, &5), 2);
+    assert_eq!(ceil_div(&11u64, &5), 3);
+    assert_eq!(ceil_div(&0u64, &5), 0);
+    assert_eq!(ceil_div(&u64::MAX, &2), 1 << 63);
+
+    let huge = BigUint::from(10u32).pow(40);
+    assert_eq!(ceil_div(&huge, &big(360)), (&huge + big(359)) / big(360));
+    assert_eq!(ceil_div(&(&huge * big(360)), &big(360)), huge);
+}
+
+#[test]
+fn isqrt_is_the_floor_of_the_square_root() {
+    assert_eq!([0u64, 1, 3, 4, 8, 9, 99, 100].map(|n| isqrt(&n)), [0, 1, 1, 2, 2, 3, 9, 10]);
+    assert_eq!(isqrt(&u64::MAX), u32::MAX as u64);
+
+    // Just below and at a perfect square far past u64
+    let root = BigUint::from(10u32).pow(30) + big(7);
+    let square = &root * &root;
+    assert_eq!(isqrt(&square), root);
+    assert_eq!(isqrt(&(square - big(1))), root - big(1));
+}